@@ -1,8 +1,81 @@
+use crate::equations::wind_speed_at_2m;
+use crate::error::ClimateError;
+use crate::gap_fill::GapFill;
+use crate::hourly::HourlyData;
 use crate::output::Output;
-use crate::units::Units;
+use crate::qc::QcFlag;
+use crate::quantity::{
+    Length, LengthUnits, Pressure, PressureUnits, Quantity, QuantityUnits, RadiantExposure,
+    RadiantExposureUnits, Ratio, RatioUnits, Speed, SpeedUnits, Temperature, TemperatureUnits,
+};
+use crate::refet::daily_refet;
+use crate::units::{Period, Units};
 use chrono::NaiveDate;
-use std::error::Error;
+use chrono_tz::Tz;
 
+/// The climate variables held by `DailyData`, used to attach QC flags and other annotations to a
+/// single value rather than the whole record.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Variable {
+    Tmin,
+    Tmax,
+    RhMin,
+    RhMax,
+    Dewpoint,
+    Precip,
+    Rs,
+    Ea,
+    WindSpeed,
+    /// station pressure, which refet otherwise estimates from the elevation
+    Pressure,
+}
+
+impl Variable {
+    pub fn name(&self) -> &str {
+        match self {
+            Variable::Tmin => "tmin",
+            Variable::Tmax => "tmax",
+            Variable::RhMin => "rhmin",
+            Variable::RhMax => "rhmax",
+            Variable::Dewpoint => "dewpoint",
+            Variable::Precip => "precip",
+            Variable::Rs => "rs",
+            Variable::Ea => "ea",
+            Variable::WindSpeed => "wind_speed",
+            Variable::Pressure => "pressure",
+        }
+    }
+
+    /// The units the variable is held in by `Output`.
+    pub fn output_units(&self) -> Units {
+        match self {
+            Variable::Tmin | Variable::Tmax | Variable::Dewpoint => Units::Celsius,
+            Variable::RhMin | Variable::RhMax => Units::Percent,
+            Variable::Precip => Units::Millimeters,
+            Variable::Rs => Units::MegaJoulesPerSquareMeterPerDay,
+            Variable::Ea | Variable::Pressure => Units::KiloPascals,
+            Variable::WindSpeed => Units::MetersPerSecond,
+        }
+    }
+
+    pub fn all() -> [Variable; 10] {
+        [
+            Variable::Tmin,
+            Variable::Tmax,
+            Variable::RhMin,
+            Variable::RhMax,
+            Variable::Dewpoint,
+            Variable::Precip,
+            Variable::Rs,
+            Variable::Ea,
+            Variable::WindSpeed,
+            Variable::Pressure,
+        ]
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug)]
 pub struct DailyData {
     pub date: NaiveDate,
@@ -24,8 +97,13 @@ pub struct DailyData {
     pub ea_units: Option<String>,
     pub wind_speed: Option<f64>,
     pub ws_units: Option<String>,
+    /// measured station pressure, used by refet instead of the pressure for the elevation
+    pub pressure: Option<f64>,
+    pub pressure_units: Option<String>,
     pub short_refet: Option<f64>,
     pub tall_refet: Option<f64>,
+    pub qc_flags: Vec<QcFlag>,
+    pub gap_fills: Vec<GapFill>,
 }
 
 impl DailyData {
@@ -47,6 +125,7 @@ impl DailyData {
     /// # Returns
     ///
     /// A new instance of `DailyData`.
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         date: NaiveDate,
         tmin: (f64, String),
@@ -58,7 +137,7 @@ impl DailyData {
         rs: Option<(f64, String)>,
         ea: Option<(f64, String)>,
         wind_speed: Option<(f64, String)>,
-    ) -> Result<DailyData, String> {
+    ) -> Result<DailyData, ClimateError> {
         let (tmin_value, tmin_units) = tmin;
         let (tmax_value, tmax_units) = tmax;
 
@@ -82,17 +161,18 @@ impl DailyData {
             ea_units: None,
             wind_speed: None,
             ws_units: None,
+            pressure: None,
+            pressure_units: None,
             short_refet: None,
             tall_refet: None,
+            qc_flags: Vec::new(),
+            gap_fills: Vec::new(),
         };
 
         if let Some((rhmin_value, rhmin_units)) = rhmin {
             daily_data.rhmin = Some(rhmin_value);
             if rhmin_units.is_empty() {
-                return Err(
-                    "Relative humidity min units must not be empty when including a value"
-                        .to_string(),
-                );
+                return Err(ClimateError::missing_units(Variable::RhMin.name(), date));
             }
 
             daily_data.rhmin_units = Some(rhmin_units);
@@ -101,10 +181,7 @@ impl DailyData {
         if let Some((rhmax_value, rhmax_units)) = rhmax {
             daily_data.rhmax = Some(rhmax_value);
             if rhmax_units.is_empty() {
-                return Err(
-                    "Relative humidity max units must not be empty when including a value"
-                        .to_string(),
-                );
+                return Err(ClimateError::missing_units(Variable::RhMax.name(), date));
             }
 
             daily_data.rhmax_units = Some(rhmax_units);
@@ -112,7 +189,7 @@ impl DailyData {
         if let Some((dewpoint_value, dewpoint_units)) = dewpoint {
             daily_data.dewpoint = Some(dewpoint_value);
             if dewpoint_units.is_empty() {
-                return Err("Dewpoint units must not be empty when including a value".to_string());
+                return Err(ClimateError::missing_units(Variable::Dewpoint.name(), date));
             }
 
             daily_data.dewpoint_units = Some(dewpoint_units);
@@ -120,9 +197,7 @@ impl DailyData {
         if let Some((precip_value, precip_units)) = precip {
             daily_data.precip = Some(precip_value);
             if precip_units.is_empty() {
-                return Err(
-                    "Precipitation units must not be empty when including a value".to_string(),
-                );
+                return Err(ClimateError::missing_units(Variable::Precip.name(), date));
             }
 
             daily_data.precip_units = Some(precip_units);
@@ -130,9 +205,7 @@ impl DailyData {
         if let Some((rs_value, rs_units)) = rs {
             daily_data.rs = Some(rs_value);
             if rs_units.is_empty() {
-                return Err(
-                    "Solar radiation units must not be empty when including a value".to_string(),
-                );
+                return Err(ClimateError::missing_units(Variable::Rs.name(), date));
             }
 
             daily_data.rs_units = Some(rs_units);
@@ -140,9 +213,7 @@ impl DailyData {
         if let Some((ea_value, ea_units)) = ea {
             daily_data.ea = Some(ea_value);
             if ea_units.is_empty() {
-                return Err(
-                    "Vapor pressure units must not be empty when including a value".to_string(),
-                );
+                return Err(ClimateError::missing_units(Variable::Ea.name(), date));
             }
 
             daily_data.ea_units = Some(ea_units);
@@ -150,7 +221,10 @@ impl DailyData {
         if let Some((ws_value, ws_units)) = wind_speed {
             daily_data.wind_speed = Some(ws_value);
             if ws_units.is_empty() {
-                return Err("Wind speed units must not be empty when including a value".to_string());
+                return Err(ClimateError::missing_units(
+                    Variable::WindSpeed.name(),
+                    date,
+                ));
             }
             daily_data.ws_units = Some(ws_units);
         }
@@ -158,127 +232,202 @@ impl DailyData {
         Ok(daily_data)
     }
 
-    fn to_output(&self) -> Result<Output, String> {
-        let mut output = Output::new();
-        output.set_date(self.date.clone());
-        output.set_tmin(convert_temp_to_c(self.tmin, &*self.tmin_units)?);
-        output.set_tmax(convert_temp_to_c(self.tmax, &*self.tmax_units)?);
-        if self.dewpoint.is_some() {
-            let d_units = self.dewpoint_units.as_ref().unwrap().clone();
-            let dewpoint_converted = convert_temp_to_c(self.dewpoint.unwrap(), &d_units)?;
-            output.set_dewpoint(Some(dewpoint_converted));
+    /// Creates a new `DailyData` from typed quantities, so each value's units are checked to be
+    /// of the right kind when compiling rather than when the record is converted.
+    #[allow(clippy::too_many_arguments)]
+    pub fn from_quantities(
+        date: NaiveDate,
+        tmin: Temperature,
+        tmax: Temperature,
+        rhmin: Option<Ratio>,
+        rhmax: Option<Ratio>,
+        dewpoint: Option<Temperature>,
+        precip: Option<Length>,
+        rs: Option<RadiantExposure>,
+        ea: Option<Pressure>,
+        wind_speed: Option<Speed>,
+    ) -> Result<DailyData, ClimateError> {
+        DailyData::new(
+            date,
+            tmin.into(),
+            tmax.into(),
+            rhmin.map(Quantity::into),
+            rhmax.map(Quantity::into),
+            dewpoint.map(Quantity::into),
+            precip.map(Quantity::into),
+            rs.map(Quantity::into),
+            ea.map(Quantity::into),
+            wind_speed.map(Quantity::into),
+        )
+    }
+
+    /// Sets the measured station pressure of the record.
+    pub fn with_pressure(mut self, pressure: (f64, String)) -> Result<DailyData, ClimateError> {
+        let (value, units) = pressure;
+        if units.is_empty() {
+            return Err(ClimateError::missing_units(
+                Variable::Pressure.name(),
+                self.date,
+            ));
         }
+        self.pressure = Some(value);
+        self.pressure_units = Some(units);
+        Ok(self)
+    }
 
-        if self.rhmin.is_some() {
-            let r_units = self.rhmin_units.as_ref().unwrap().clone();
-            let r_unit =
-                Units::from_abbreviation(&r_units).expect("Invalid units for relative humidity");
-            match r_unit {
-                Units::Percent => {
-                    output.set_rhmin(Some(self.rhmin.unwrap()));
-                }
-                _ => {
-                    return Err(
-                        "Invalid units for relative humidity min, must be percent".to_string()
-                    )
-                }
-            }
+    /// Attaches a QC flag to one value of this record, the value itself is left in place so the
+    /// caller can decide whether to trust it. A flag identical to one already present is ignored.
+    pub fn add_flag(&mut self, flag: QcFlag) {
+        if !self.qc_flags.contains(&flag) {
+            self.qc_flags.push(flag);
         }
+    }
 
-        if self.rhmax.is_some() {
-            let r_units = self.rhmax_units.as_ref().unwrap().clone();
-            let r_unit =
-                Units::from_abbreviation(&r_units).expect("Invalid units for relative humidity");
-            match r_unit {
-                Units::Percent => {
-                    output.set_rhmax(Some(self.rhmin.unwrap()));
-                }
-                _ => {
-                    return Err(
-                        "Invalid units for relative humidity max, must be percent".to_string()
-                    )
-                }
-            }
+    /// Returns the QC flags attached to a single variable.
+    pub fn flags_for(&self, variable: Variable) -> Vec<&QcFlag> {
+        self.qc_flags
+            .iter()
+            .filter(|flag| flag.variable == variable)
+            .collect()
+    }
+
+    pub fn is_flagged(&self, variable: Variable) -> bool {
+        self.qc_flags.iter().any(|flag| flag.variable == variable)
+    }
+
+    /// Returns a variable as a typed quantity in the units it was given in, or `None` when
+    /// missing. The units must be the kind the variable is measured in.
+    pub fn quantity<U: QuantityUnits>(
+        &self,
+        variable: Variable,
+    ) -> Result<Option<Quantity<U>>, ClimateError> {
+        match self.value_with_units(variable)? {
+            Some((value, units)) => Quantity::parse(value, units)
+                .map(Some)
+                .map_err(|e| e.with_context(variable.name(), self.date)),
+            None => Ok(None),
         }
+    }
 
-        if self.ea.is_some() {
-            let ea_units = self.ea_units.as_ref().unwrap().clone();
-            let ea_unit =
-                Units::from_abbreviation(&ea_units).expect("Invalid units for vapor pressure");
-            match ea_unit {
-                Units::KiloPascals => output.set_ea(Some(self.ea.unwrap())),
-                Units::Pascals => output.set_ea(Some(
-                    Units::Pascals
-                        .convert(self.ea.unwrap(), &Units::KiloPascals)
-                        .expect("Units conversion failed"),
-                )),
-                _ => panic!("Invalid units for ea: {}", ea_units),
-            }
+    /// Returns a variable converted to the units `Output` holds it in, or `None` when missing.
+    pub fn value_in_output_units(&self, variable: Variable) -> Result<Option<f64>, ClimateError> {
+        match self.value_with_units(variable)? {
+            Some((value, units)) => convert_to_output_units(variable, value, units)
+                .map(Some)
+                .map_err(|e| e.with_context(variable.name(), self.date)),
+            None => Ok(None),
         }
+    }
 
-        if self.rs.is_some() {
-            let rs_units = self.rs_units.as_ref().unwrap().clone();
-            let rs_unit =
-                Units::from_abbreviation(&rs_units).expect("Invalid units for solar radiation");
-            match rs_unit {
-                Units::MegaJoulesPerSquareMeter => output.set_rs(Some(self.rs.unwrap())),
-                Units::WattsPerSquareMeter => output.set_rs(Some(
-                    Units::WattsPerSquareMeter
-                        .convert(self.rs.unwrap(), &Units::MegaJoulesPerSquareMeter)
-                        .expect("Units conversion failed"),
-                )),
-                Units::Langley => output.set_rs(Some(
-                    Units::Langley
-                        .convert(self.rs.unwrap(), &Units::MegaJoulesPerSquareMeter)
-                        .expect("Units conversion failed"),
-                )),
-                _ => panic!("Invalid units for rs: {}", rs_units),
-            }
+    /// A variable's value and units, values set directly on the struct may be missing units.
+    fn value_with_units(&self, variable: Variable) -> Result<Option<(f64, &str)>, ClimateError> {
+        let (value, units) = match variable {
+            Variable::Tmin => (Some(self.tmin), Some(&self.tmin_units)),
+            Variable::Tmax => (Some(self.tmax), Some(&self.tmax_units)),
+            Variable::RhMin => (self.rhmin, self.rhmin_units.as_ref()),
+            Variable::RhMax => (self.rhmax, self.rhmax_units.as_ref()),
+            Variable::Dewpoint => (self.dewpoint, self.dewpoint_units.as_ref()),
+            Variable::Precip => (self.precip, self.precip_units.as_ref()),
+            Variable::Rs => (self.rs, self.rs_units.as_ref()),
+            Variable::Ea => (self.ea, self.ea_units.as_ref()),
+            Variable::WindSpeed => (self.wind_speed, self.ws_units.as_ref()),
+            Variable::Pressure => (self.pressure, self.pressure_units.as_ref()),
+        };
+
+        match (value, units) {
+            (None, _) => Ok(None),
+            (Some(value), Some(units)) if !units.is_empty() => Ok(Some((value, units.as_str()))),
+            (Some(_), _) => Err(ClimateError::missing_units(variable.name(), self.date)),
+        }
+    }
+
+    /// Sets a variable to a value in the given units.
+    pub fn set_value(&mut self, variable: Variable, value: f64, units: String) {
+        match variable {
+            Variable::Tmin => (self.tmin, self.tmin_units) = (value, units),
+            Variable::Tmax => (self.tmax, self.tmax_units) = (value, units),
+            Variable::RhMin => (self.rhmin, self.rhmin_units) = (Some(value), Some(units)),
+            Variable::RhMax => (self.rhmax, self.rhmax_units) = (Some(value), Some(units)),
+            Variable::Dewpoint => (self.dewpoint, self.dewpoint_units) = (Some(value), Some(units)),
+            Variable::Precip => (self.precip, self.precip_units) = (Some(value), Some(units)),
+            Variable::Rs => (self.rs, self.rs_units) = (Some(value), Some(units)),
+            Variable::Ea => (self.ea, self.ea_units) = (Some(value), Some(units)),
+            Variable::WindSpeed => (self.wind_speed, self.ws_units) = (Some(value), Some(units)),
+            Variable::Pressure => (self.pressure, self.pressure_units) = (Some(value), Some(units)),
         }
+    }
 
-        if self.wind_speed.is_some() {
-            let ws_units = self.ws_units.as_ref().unwrap().clone();
-            let ws_unit =
-                Units::from_abbreviation(&ws_units).expect("Invalid units for wind speed");
-            match ws_unit {
-                Units::MetersPerSecond => output.set_ws(Some(self.wind_speed.unwrap())),
-                Units::MilesPerHour => output.set_ws(Some(
-                    Units::MilesPerHour
-                        .convert(self.wind_speed.unwrap(), &Units::MetersPerSecond)
-                        .expect("Units conversion failed"),
-                )),
-                Units::Miles => output.set_ws(Some(
-                    Units::Miles
-                        .convert(self.wind_speed.unwrap(), &Units::MetersPerSecond)
-                        .expect("Units conversion failed"),
-                )),
-                Units::Meters => output.set_ws(Some(
-                    Units::Meters
-                        .convert(self.wind_speed.unwrap(), &Units::MetersPerSecond)
-                        .expect("Units conversion failed"),
-                )),
-                Units::Kilometers => output.set_ws(Some(
-                    Units::Kilometers
-                        .convert(self.wind_speed.unwrap(), &Units::MetersPerSecond)
-                        .expect("Units conversion failed"),
-                )),
-                _ => panic!("Invalid units for ws: {}", ws_units),
-            }
+    fn to_output(&self) -> Result<Output, ClimateError> {
+        let value = |variable: Variable| self.value_in_output_units(variable);
+
+        let mut output = Output::new();
+        output.set_date(self.date);
+        if let (Some(tmin), Some(tmax)) = (value(Variable::Tmin)?, value(Variable::Tmax)?) {
+            output.set_tmin(tmin);
+            output.set_tmax(tmax);
         }
+        output.set_rhmin(value(Variable::RhMin)?);
+        output.set_rhmax(value(Variable::RhMax)?);
+        output.set_dewpoint(value(Variable::Dewpoint)?);
+        output.set_precip(value(Variable::Precip)?);
+        output.set_rs(value(Variable::Rs)?);
+        output.set_ea(value(Variable::Ea)?);
+        output.set_ws(value(Variable::WindSpeed)?);
+        output.set_pressure(value(Variable::Pressure)?);
 
         Ok(output)
     }
 }
 
-fn convert_temp_to_c(value: f64, actual_units: &str) -> Result<f64, String> {
-    let tmin_unit = Units::from_abbreviation(actual_units)?;
-    match tmin_unit {
-        Units::Celsius => Ok(value),
-        Units::Fahrenheit => Ok(Units::Fahrenheit.convert(value, &Units::Celsius)?),
-        _ => Err(format!("Invalid units for temperature: {}", actual_units)),
+/// Converts a value of any variable to the units `Output` holds it in.
+pub(crate) fn convert_to_output_units(
+    variable: Variable,
+    value: f64,
+    units: &str,
+) -> Result<f64, ClimateError> {
+    match variable {
+        Variable::Tmin | Variable::Tmax | Variable::Dewpoint => convert_temp_to_c(value, units),
+        Variable::RhMin | Variable::RhMax => check_rh_units(value, units),
+        Variable::Precip => convert_precip_to_mm(value, units),
+        Variable::Rs => convert_rs_to_mj(value, units),
+        Variable::Ea | Variable::Pressure => convert_ea_to_kpa(value, units),
+        Variable::WindSpeed => convert_ws_to_ms(value, units),
     }
 }
 
+pub(crate) fn convert_temp_to_c(value: f64, actual_units: &str) -> Result<f64, ClimateError> {
+    Temperature::parse(value, actual_units)?.value_in(TemperatureUnits::Celsius)
+}
+
+/// Converts solar radiation to MJ/m²/day, a total without a period is taken as the day's total
+/// and W/m² as the mean flux over the day.
+pub(crate) fn convert_rs_to_mj(value: f64, actual_units: &str) -> Result<f64, ClimateError> {
+    RadiantExposure::parse(value, actual_units)?
+        .over(Period::Day)
+        .value_in(RadiantExposureUnits::MegaJoulesPerSquareMeterPerDay)
+}
+
+/// Converts vapor or station pressure to kPa using the same conversions as the output.
+pub(crate) fn convert_ea_to_kpa(value: f64, actual_units: &str) -> Result<f64, ClimateError> {
+    Pressure::parse(value, actual_units)?.value_in(PressureUnits::KiloPascals)
+}
+
+/// Converts wind speed to m/s at the height it was measured.
+pub(crate) fn convert_ws_to_ms(value: f64, actual_units: &str) -> Result<f64, ClimateError> {
+    Speed::parse(value, actual_units)?.value_in(SpeedUnits::MetersPerSecond)
+}
+
+/// Converts precipitation to mm.
+pub(crate) fn convert_precip_to_mm(value: f64, actual_units: &str) -> Result<f64, ClimateError> {
+    Length::parse(value, actual_units)?.value_in(LengthUnits::Millimeters)
+}
+
+/// Checks relative humidity is given in percent, the only unit the output accepts.
+pub(crate) fn check_rh_units(value: f64, actual_units: &str) -> Result<f64, ClimateError> {
+    Ratio::parse(value, actual_units)?.value_in(RatioUnits::Percent)
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug)]
 pub struct StationData {
     pub name: String,
@@ -289,6 +438,9 @@ pub struct StationData {
     pub wind_height: f64,
     pub daily_data: Vec<DailyData>,
     pub id: Option<i32>,
+    /// the station's time zone, hourly timestamps are kept in its standard time
+    pub time_zone: Option<Tz>,
+    pub hourly_data: Vec<HourlyData>,
 }
 
 impl StationData {
@@ -310,9 +462,18 @@ impl StationData {
             wind_height,
             daily_data: Vec::new(),
             id,
+            time_zone: None,
+            hourly_data: Vec::new(),
         }
     }
 
+    /// Sets the time zone of the station, used to move hourly timestamps to standard time.
+    pub fn with_time_zone(mut self, time_zone: Tz) -> StationData {
+        self.time_zone = Some(time_zone);
+        self
+    }
+
+    #[allow(clippy::too_many_arguments)]
     pub fn add_daily_data(
         &mut self,
         date: NaiveDate,
@@ -325,7 +486,7 @@ impl StationData {
         radiation_solar: (f64, String), // (value, units)
         ea: (f64, String),              // (value, units)
         wind_speed: (f64, String),      // (value, units)
-    ) -> Result<(), Box<dyn Error>> {
+    ) -> Result<(), ClimateError> {
         let daily_data = DailyData::new(
             date,
             tmin,
@@ -342,11 +503,38 @@ impl StationData {
         Ok(())
     }
 
+    /// Great-circle distance to another station in kilometers.
+    pub fn distance_to(&self, other: &StationData) -> f64 {
+        const EARTH_RADIUS_KM: f64 = 6371.0;
+        let (lat1, lat2) = (self.latitude.to_radians(), other.latitude.to_radians());
+        let d_lat = lat2 - lat1;
+        let d_long = (other.longitude - self.longitude).to_radians();
+        let a =
+            (d_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (d_long / 2.0).sin().powi(2);
+        2.0 * EARTH_RADIUS_KM * a.sqrt().asin()
+    }
+
     pub fn add_daily_records(&mut self, records: Vec<DailyData>) {
         self.daily_data = records;
     }
 
-    pub fn to_output(&self) -> Result<Vec<Output>, String> {
+    /// Adjusts a wind speed measured at the station's wind height to 2 m. The log profile is
+    /// undefined at 0.08 m and below, so such a height is an error when there is wind to adjust.
+    pub(crate) fn ws_at_2m(&self, ws: Option<f64>) -> Result<Option<f64>, ClimateError> {
+        match ws {
+            Some(ws) if self.wind_height > 0.08 => Ok(Some(wind_speed_at_2m(ws, self.wind_height))),
+            Some(_) => Err(ClimateError::invalid_value(format!(
+                "Wind height of {} m is not above 0.08 m",
+                self.wind_height
+            ))
+            .for_station(&self.name)),
+            None => Ok(None),
+        }
+    }
+
+    /// Converts the daily records to `Output` in SI units with the station's location, wind speed
+    /// is adjusted from the station's wind height to 2 m.
+    pub fn to_output(&self) -> Result<Vec<Output>, ClimateError> {
         let mut result: Vec<Output> = Vec::new();
 
         for daily_data in &self.daily_data {
@@ -354,11 +542,334 @@ impl StationData {
 
             output.set_latitude(self.latitude);
             output.set_z(self.elevation);
-            output.set_wz(Some(self.wind_height));
+            // wind is measured at the station's height, the output holds it at 2 m
+            output.set_ws(
+                self.ws_at_2m(output.get_ws())
+                    .map_err(|e| e.with_context("wind_speed", daily_data.date))?,
+            );
+            output.set_wz(Some(2.0));
+            output.set_station_id(self.id);
+            output.set_station_name(self.name.clone());
 
             result.push(output);
         }
 
         Ok(result)
     }
+
+    /// Computes the daily ASCE standardized reference ET for every record, storing ETos in
+    /// `short_refet` and ETrs in `tall_refet`. Days missing rs, wind speed or humidity are left
+    /// empty. Returns the number of days that were computed.
+    pub fn calculate_refet(&mut self) -> Result<usize, ClimateError> {
+        let outputs = self.to_output()?;
+        let mut computed = 0;
+        for (daily_data, output) in self.daily_data.iter_mut().zip(outputs) {
+            match daily_refet(&output) {
+                Ok(refet) => {
+                    daily_data.short_refet = Some(refet.etos);
+                    daily_data.tall_refet = Some(refet.etrs);
+                    computed += 1;
+                }
+                Err(_) => {
+                    daily_data.short_refet = None;
+                    daily_data.tall_refet = None;
+                }
+            }
+        }
+
+        Ok(computed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn output_carries_precip_and_station() {
+        let mut station = StationData::new(
+            "Mead".to_string(),
+            "test".to_string(),
+            41.0,
+            -96.0,
+            350.0,
+            2.0,
+            Some(12),
+        );
+        let date = NaiveDate::from_ymd_opt(2023, 7, 1).unwrap();
+        station.add_daily_records(vec![DailyData::new(
+            date,
+            (15.0, "°C".to_string()),
+            (30.0, "°C".to_string()),
+            None,
+            None,
+            None,
+            Some((0.5, "in".to_string())),
+            None,
+            None,
+            None,
+        )
+        .unwrap()]);
+
+        let output = station.to_output().unwrap();
+        assert_eq!(output[0].get_precip(), Some(12.7));
+        assert_eq!(output[0].get_station_id(), Some(12));
+        assert_eq!(output[0].get_station_name(), "Mead");
+    }
+
+    #[test]
+    fn refet_is_stored_on_daily_data() {
+        let mut station = StationData::new(
+            "Brussels".to_string(),
+            "test".to_string(),
+            50.8,
+            4.35,
+            100.0,
+            10.0,
+            None,
+        );
+        let records = [6, 7]
+            .iter()
+            .map(|day| {
+                let rs = (*day == 6).then(|| (22.07, "MJ/m²".to_string()));
+                DailyData::new(
+                    NaiveDate::from_ymd_opt(2023, 7, *day).unwrap(),
+                    (12.3, "°C".to_string()),
+                    (21.5, "°C".to_string()),
+                    Some((63.0, "%".to_string())),
+                    Some((84.0, "%".to_string())),
+                    None,
+                    None,
+                    rs,
+                    None,
+                    Some((10.0 / 3.6, "m/s".to_string())),
+                )
+                .unwrap()
+            })
+            .collect();
+        station.add_daily_records(records);
+
+        // 10 km/h at 10 m is 2.08 m/s at 2 m
+        let output = &station.to_output().unwrap()[0];
+        assert_eq!((output.get_ws().unwrap() * 100.0).round() / 100.0, 2.08);
+        assert_eq!(output.get_wz(), 2.0);
+
+        assert_eq!(station.calculate_refet().unwrap(), 1);
+        let etos = station.daily_data[0].short_refet.unwrap();
+        assert_eq!((etos * 10.0).round() / 10.0, 3.9);
+        assert_eq!(station.daily_data[1].tall_refet, None);
+
+        // the log profile has no value at or below 0.08 m
+        station.wind_height = 0.0;
+        let error = station.to_output().err().unwrap();
+        assert_eq!(error.variable(), Some("wind_speed"));
+        assert!(
+            error.to_string().contains("Wind height of 0 m"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn records_from_typed_quantities() {
+        let date = NaiveDate::from_ymd_opt(2023, 7, 1).unwrap();
+        let daily_data = DailyData::from_quantities(
+            date,
+            Temperature::new(59.0, TemperatureUnits::Fahrenheit),
+            Temperature::new(30.0, TemperatureUnits::Celsius),
+            None,
+            None,
+            None,
+            Some(Length::new(0.5, LengthUnits::Inches)),
+            None,
+            None,
+            Some(Speed::new(2.0, SpeedUnits::MetersPerSecond)),
+        )
+        .unwrap();
+        assert_eq!(daily_data.tmin_units, "°F");
+        assert_eq!(
+            daily_data.value_in_output_units(Variable::Tmin),
+            Ok(Some(15.0))
+        );
+
+        let precip = daily_data
+            .quantity::<LengthUnits>(Variable::Precip)
+            .unwrap();
+        assert_eq!(precip, Some(Length::new(0.5, LengthUnits::Inches)));
+        assert_eq!(daily_data.quantity::<RatioUnits>(Variable::RhMin), Ok(None));
+        let error = daily_data
+            .quantity::<TemperatureUnits>(Variable::WindSpeed)
+            .unwrap_err();
+        assert_eq!(error.variable(), Some("wind_speed"));
+    }
+
+    #[test]
+    fn station_pressure_is_used_for_refet() {
+        let date = NaiveDate::from_ymd_opt(2023, 7, 6).unwrap();
+        let daily_data = DailyData::new(
+            date,
+            (12.3, "°C".to_string()),
+            (21.5, "°C".to_string()),
+            Some((63.0, "%".to_string())),
+            Some((84.0, "%".to_string())),
+            None,
+            None,
+            Some((22.07, "MJ/m²".to_string())),
+            None,
+            Some((2.78, "m/s".to_string())),
+        )
+        .unwrap();
+        let mut output = daily_data.to_output().unwrap();
+        output.set_z(100.0);
+        let estimated = daily_refet(&output).unwrap();
+        assert_eq!((estimated.terms.pressure * 10.0).round() / 10.0, 100.1);
+
+        let output = daily_data
+            .with_pressure((29.5, "inHg".to_string()))
+            .unwrap()
+            .to_output()
+            .unwrap();
+        assert_eq!(
+            (output.get_pressure().unwrap() * 100.0).round() / 100.0,
+            99.9
+        );
+        let measured = daily_refet(&output).unwrap();
+        assert_eq!(measured.terms.pressure, output.get_pressure().unwrap());
+        assert!(measured.terms.gamma < estimated.terms.gamma);
+    }
+
+    #[test]
+    fn bad_units_are_errors_not_panics() {
+        let date = NaiveDate::from_ymd_opt(2023, 7, 1).unwrap();
+        let mut station = StationData::new(
+            "Test".to_string(),
+            "test".to_string(),
+            41.0,
+            -96.0,
+            350.0,
+            2.0,
+            None,
+        );
+        station
+            .add_daily_data(
+                date,
+                (15.0, "°C".to_string()),
+                (30.0, "°C".to_string()),
+                (25.0, "%".to_string()),
+                (80.0, "%".to_string()),
+                (12.0, "°C".to_string()),
+                (0.0, "mm".to_string()),
+                (25.0, "MJ/m²".to_string()),
+                (1.2, "°C".to_string()),
+                (2.0, "m/s".to_string()),
+            )
+            .unwrap();
+
+        let error = station.to_output().err().unwrap();
+        assert_eq!(
+            error,
+            ClimateError::UnsupportedConversion {
+                from: "Celsius".to_string(),
+                to: "KiloPascals".to_string(),
+                variable: Some("ea".to_string()),
+                date: Some(date),
+            }
+        );
+
+        station.daily_data[0].ea_units = Some("kPa".to_string());
+        station.daily_data[0].ws_units = None;
+        let error = station.to_output().err().unwrap();
+        assert_eq!(error, ClimateError::missing_units("wind_speed", date));
+
+        station.daily_data[0].ws_units = Some("knots per fortnight".to_string());
+        let error = station.to_output().err().unwrap();
+        assert_eq!(error.variable(), Some("wind_speed"));
+
+        let missing = DailyData::new(
+            date,
+            (15.0, "°C".to_string()),
+            (30.0, "°C".to_string()),
+            None,
+            None,
+            None,
+            Some((3.0, String::new())),
+            None,
+            None,
+            None,
+        );
+        assert_eq!(
+            missing.err(),
+            Some(ClimateError::missing_units("precip", date))
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn station_data_round_trips_through_json() {
+        let date = NaiveDate::from_ymd_opt(2023, 7, 6).unwrap();
+        let mut daily_data = DailyData::new(
+            date,
+            (54.1, "°F".to_string()),
+            (70.7, "°F".to_string()),
+            Some((63.0, "%".to_string())),
+            None,
+            None,
+            Some((0.1, "in".to_string())),
+            Some((22.07, "MJ/m²".to_string())),
+            None,
+            Some((2.78, "m/s".to_string())),
+        )
+        .unwrap()
+        .with_pressure((29.5, "inHg".to_string()))
+        .unwrap();
+        daily_data.short_refet = Some(5.718);
+        daily_data.qc_flags.push(QcFlag::new(
+            Variable::Rs,
+            crate::qc::FlagKind::ExceedsClearSky,
+            "rs is above rso".to_string(),
+        ));
+        let mut station = StationData::new(
+            "Brussels".to_string(),
+            "test".to_string(),
+            50.8,
+            4.35,
+            100.0,
+            10.0,
+            Some(3),
+        )
+        .with_time_zone(chrono_tz::Europe::Brussels);
+        station.add_daily_records(vec![daily_data]);
+        station.add_hourly_records(vec![HourlyData::new(
+            date.and_hms_opt(13, 0, 0).unwrap(),
+            (21.5, "°C".to_string()),
+            Some((63.0, "%".to_string())),
+            None,
+            None,
+            Some((712.5, "W/m²".to_string())),
+            None,
+            None,
+        )
+        .unwrap()]);
+
+        let json = serde_json::to_string(&station).unwrap();
+        assert!(json.contains("\"date\":\"2023-07-06\""));
+        let read: StationData = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&read).unwrap(), json);
+        assert_eq!(read.time_zone, Some(chrono_tz::Europe::Brussels));
+        assert_eq!(read.daily_data[0].qc_flags, station.daily_data[0].qc_flags);
+        assert_eq!(read.daily_data[0].pressure, Some(29.5));
+        assert_eq!(
+            read.hourly_data[0].timestamp,
+            station.hourly_data[0].timestamp
+        );
+
+        let output = read.to_output().unwrap();
+        let json = serde_json::to_string(&output[0]).unwrap();
+        let output: Output = serde_json::from_str(&json).unwrap();
+        assert_eq!(output.get_station_name(), "Brussels");
+
+        assert_eq!(serde_json::to_string(&Units::Fahrenheit).unwrap(), "\"°F\"");
+        let units: Units = serde_json::from_str("\"W m-2\"").unwrap();
+        assert_eq!(units, Units::WattsPerSquareMeter);
+    }
 }
@@ -0,0 +1,582 @@
+use crate::data_model::{DailyData, StationData};
+use crate::error::ClimateError;
+use chrono::NaiveDate;
+
+/// A database column and the units its values are stored in, e.g. `("tmax_f", "°F")`.
+#[derive(Debug, Clone)]
+pub struct ColumnMapping {
+    pub column: String,
+    pub units: String,
+}
+
+impl ColumnMapping {
+    pub fn new(column: &str, units: &str) -> ColumnMapping {
+        ColumnMapping {
+            column: column.to_string(),
+            units: units.to_string(),
+        }
+    }
+}
+
+/// Describes where the station and daily tables live and which columns hold each variable.
+///
+/// Table and column names are written into the SQL as given so schema qualified names such as
+/// `climate.stations` work; they must come from trusted configuration, never from user input.
+/// Optional variables that are `None` are not queried and are left empty on the `DailyData`.
+#[derive(Debug, Clone)]
+pub struct TableMapping {
+    pub station_table: String,
+    pub station_id_column: String,
+    pub name_column: String,
+    pub source_column: String,
+    pub latitude_column: String,
+    pub longitude_column: String,
+    pub elevation_column: String,
+    pub wind_height_column: String,
+    pub daily_table: String,
+    pub daily_station_id_column: String,
+    pub date_column: String,
+    pub tmin: ColumnMapping,
+    pub tmax: ColumnMapping,
+    pub rhmin: Option<ColumnMapping>,
+    pub rhmax: Option<ColumnMapping>,
+    pub dewpoint: Option<ColumnMapping>,
+    pub precip: Option<ColumnMapping>,
+    pub rs: Option<ColumnMapping>,
+    pub ea: Option<ColumnMapping>,
+    pub wind_speed: Option<ColumnMapping>,
+    pub pressure: Option<ColumnMapping>,
+}
+
+impl Default for TableMapping {
+    fn default() -> Self {
+        TableMapping {
+            station_table: "stations".to_string(),
+            station_id_column: "id".to_string(),
+            name_column: "name".to_string(),
+            source_column: "source".to_string(),
+            latitude_column: "latitude".to_string(),
+            longitude_column: "longitude".to_string(),
+            elevation_column: "elevation".to_string(),
+            wind_height_column: "wind_height".to_string(),
+            daily_table: "daily_data".to_string(),
+            daily_station_id_column: "station_id".to_string(),
+            date_column: "date".to_string(),
+            tmin: ColumnMapping::new("tmin", "°C"),
+            tmax: ColumnMapping::new("tmax", "°C"),
+            rhmin: Some(ColumnMapping::new("rhmin", "%")),
+            rhmax: Some(ColumnMapping::new("rhmax", "%")),
+            dewpoint: Some(ColumnMapping::new("dewpoint", "°C")),
+            precip: Some(ColumnMapping::new("precip", "mm")),
+            rs: Some(ColumnMapping::new("rs", "MJ/m²")),
+            ea: Some(ColumnMapping::new("ea", "kPa")),
+            wind_speed: Some(ColumnMapping::new("wind_speed", "m/s")),
+            pressure: None,
+        }
+    }
+}
+
+impl TableMapping {
+    /// Query returning one row per station: id, name, source, latitude, longitude, elevation and
+    /// wind height. `$1` is an `int4[]` of station ids; a null wind height defaults to 2 m.
+    pub fn station_query(&self) -> String {
+        format!(
+            "SELECT {id}::int4, {name}::text, {source}::text, {lat}::float8, {long}::float8, \
+             {elev}::float8, COALESCE({wind}::float8, 2.0) FROM {table} WHERE {id} = ANY($1)",
+            id = self.station_id_column,
+            name = self.name_column,
+            source = self.source_column,
+            lat = self.latitude_column,
+            long = self.longitude_column,
+            elev = self.elevation_column,
+            wind = self.wind_height_column,
+            table = self.station_table,
+        )
+    }
+
+    /// Query returning the daily records for a set of stations between two dates, inclusive.
+    /// `$1` is an `int4[]` of station ids, `$2` and `$3` are the start and end dates. Columns are
+    /// returned in the order of `DailyRow`, with unmapped variables selected as null.
+    pub fn daily_query(&self) -> String {
+        let columns: Vec<String> = [
+            Some(&self.tmin),
+            Some(&self.tmax),
+            self.rhmin.as_ref(),
+            self.rhmax.as_ref(),
+            self.dewpoint.as_ref(),
+            self.precip.as_ref(),
+            self.rs.as_ref(),
+            self.ea.as_ref(),
+            self.wind_speed.as_ref(),
+            self.pressure.as_ref(),
+        ]
+        .iter()
+        .map(|mapping| match mapping {
+            Some(mapping) => format!("{}::float8", mapping.column),
+            None => "NULL::float8".to_string(),
+        })
+        .collect();
+
+        format!(
+            "SELECT {station}::int4, {date}::date, {columns} FROM {table} \
+             WHERE {station} = ANY($1) AND {date} BETWEEN $2 AND $3 ORDER BY {station}, {date}",
+            station = self.daily_station_id_column,
+            date = self.date_column,
+            columns = columns.join(", "),
+            table = self.daily_table,
+        )
+    }
+}
+
+/// Station metadata as read from the station table.
+#[derive(Debug, Clone)]
+pub struct StationRow {
+    pub id: i32,
+    pub name: String,
+    pub source: String,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub elevation: f64,
+    pub wind_height: f64,
+}
+
+/// A single day of values as read from the daily table, in the units given by the `TableMapping`.
+#[derive(Debug, Clone)]
+pub struct DailyRow {
+    pub station_id: i32,
+    pub date: NaiveDate,
+    pub tmin: Option<f64>,
+    pub tmax: Option<f64>,
+    pub rhmin: Option<f64>,
+    pub rhmax: Option<f64>,
+    pub dewpoint: Option<f64>,
+    pub precip: Option<f64>,
+    pub rs: Option<f64>,
+    pub ea: Option<f64>,
+    pub wind_speed: Option<f64>,
+    pub pressure: Option<f64>,
+}
+
+/// Anything that can return station and daily rows. This is implemented for `postgres::Client`
+/// and can be implemented by an in-memory stand-in for testing.
+pub trait ClimateSource {
+    fn fetch_stations(
+        &mut self,
+        mapping: &TableMapping,
+        ids: &[i32],
+    ) -> Result<Vec<StationRow>, ClimateError>;
+
+    fn fetch_daily(
+        &mut self,
+        mapping: &TableMapping,
+        ids: &[i32],
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<Vec<DailyRow>, ClimateError>;
+}
+
+#[cfg(feature = "postgres")]
+impl ClimateSource for postgres::Client {
+    fn fetch_stations(
+        &mut self,
+        mapping: &TableMapping,
+        ids: &[i32],
+    ) -> Result<Vec<StationRow>, ClimateError> {
+        let rows = self
+            .query(mapping.station_query().as_str(), &[&ids])
+            .map_err(|e| ClimateError::Database(format!("Station query failed: {}", e)))?;
+
+        rows.iter()
+            .map(|row| {
+                Ok(StationRow {
+                    id: row.try_get(0).map_err(database_error)?,
+                    name: row.try_get(1).map_err(database_error)?,
+                    source: row.try_get(2).map_err(database_error)?,
+                    latitude: row.try_get(3).map_err(database_error)?,
+                    longitude: row.try_get(4).map_err(database_error)?,
+                    elevation: row.try_get(5).map_err(database_error)?,
+                    wind_height: row.try_get(6).map_err(database_error)?,
+                })
+            })
+            .collect()
+    }
+
+    fn fetch_daily(
+        &mut self,
+        mapping: &TableMapping,
+        ids: &[i32],
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<Vec<DailyRow>, ClimateError> {
+        let rows = self
+            .query(mapping.daily_query().as_str(), &[&ids, &start, &end])
+            .map_err(|e| ClimateError::Database(format!("Daily data query failed: {}", e)))?;
+
+        rows.iter()
+            .map(|row| {
+                Ok(DailyRow {
+                    station_id: row.try_get(0).map_err(database_error)?,
+                    date: row.try_get(1).map_err(database_error)?,
+                    tmin: row.try_get(2).map_err(database_error)?,
+                    tmax: row.try_get(3).map_err(database_error)?,
+                    rhmin: row.try_get(4).map_err(database_error)?,
+                    rhmax: row.try_get(5).map_err(database_error)?,
+                    dewpoint: row.try_get(6).map_err(database_error)?,
+                    precip: row.try_get(7).map_err(database_error)?,
+                    rs: row.try_get(8).map_err(database_error)?,
+                    ea: row.try_get(9).map_err(database_error)?,
+                    wind_speed: row.try_get(10).map_err(database_error)?,
+                    pressure: row.try_get(11).map_err(database_error)?,
+                })
+            })
+            .collect()
+    }
+}
+
+/// The result of a load. Daily rows that could not become a `DailyData` record are skipped and
+/// kept in `errors`, each naming its station.
+#[derive(Debug)]
+pub struct LoadReport {
+    pub stations: Vec<StationData>,
+    pub errors: Vec<ClimateError>,
+}
+
+/// Loads a single station and its daily records between `start` and `end`, inclusive.
+pub fn load_station(
+    source: &mut impl ClimateSource,
+    mapping: &TableMapping,
+    id: i32,
+    start: NaiveDate,
+    end: NaiveDate,
+) -> Result<LoadReport, ClimateError> {
+    load_stations(source, mapping, &[id], start, end)
+}
+
+/// Loads several stations and their daily records between `start` and `end`, inclusive. The
+/// stations are returned in the order of `ids`, an id given more than once is loaded once. Days
+/// missing either tmin or tmax can't form a `DailyData` record, they and rows for stations that
+/// weren't asked for are skipped and reported in `LoadReport::errors`.
+pub fn load_stations(
+    source: &mut impl ClimateSource,
+    mapping: &TableMapping,
+    ids: &[i32],
+    start: NaiveDate,
+    end: NaiveDate,
+) -> Result<LoadReport, ClimateError> {
+    if start > end {
+        return Err(ClimateError::invalid_value(format!(
+            "Start date {} must not be after end date {}",
+            start, end
+        )));
+    }
+
+    let mut unique_ids: Vec<i32> = Vec::with_capacity(ids.len());
+    for id in ids {
+        if !unique_ids.contains(id) {
+            unique_ids.push(*id);
+        }
+    }
+    let ids = unique_ids.as_slice();
+
+    let station_rows = source.fetch_stations(mapping, ids)?;
+    let mut stations = Vec::with_capacity(ids.len());
+    for id in ids {
+        let row = station_rows
+            .iter()
+            .find(|row| row.id == *id)
+            .ok_or(ClimateError::Database(format!(
+                "Station {} was not found in {}",
+                id, mapping.station_table
+            )))?;
+
+        stations.push(StationData::new(
+            row.name.clone(),
+            row.source.clone(),
+            row.latitude,
+            row.longitude,
+            row.elevation,
+            row.wind_height,
+            Some(row.id),
+        ));
+    }
+
+    let mut errors = Vec::new();
+    for row in source.fetch_daily(mapping, ids, start, end)? {
+        let station_id = row.station_id.to_string();
+        let station = match stations.iter_mut().find(|s| s.id == Some(row.station_id)) {
+            Some(station) => station,
+            None => {
+                errors.push(
+                    ClimateError::invalid_value(format!(
+                        "Daily row on {} for a station that wasn't requested",
+                        row.date
+                    ))
+                    .for_station(&station_id),
+                );
+                continue;
+            }
+        };
+
+        let (tmin, tmax) = match (row.tmin, row.tmax) {
+            (Some(tmin), Some(tmax)) => (tmin, tmax),
+            (tmin, _) => {
+                let missing = if tmin.is_none() { "tmin" } else { "tmax" };
+                errors.push(
+                    ClimateError::invalid_value(format!("{} is required", missing))
+                        .with_context(missing, row.date)
+                        .for_station(&station_id),
+                );
+                continue;
+            }
+        };
+
+        let pressure = with_units(row.pressure, &mapping.pressure);
+        let daily_data = DailyData::new(
+            row.date,
+            (tmin, mapping.tmin.units.clone()),
+            (tmax, mapping.tmax.units.clone()),
+            with_units(row.rhmin, &mapping.rhmin),
+            with_units(row.rhmax, &mapping.rhmax),
+            with_units(row.dewpoint, &mapping.dewpoint),
+            with_units(row.precip, &mapping.precip),
+            with_units(row.rs, &mapping.rs),
+            with_units(row.ea, &mapping.ea),
+            with_units(row.wind_speed, &mapping.wind_speed),
+        )
+        .and_then(|daily_data| match pressure {
+            Some(pressure) => daily_data.with_pressure(pressure),
+            None => Ok(daily_data),
+        })
+        .map_err(|e| e.for_station(&station_id))?;
+
+        station.daily_data.push(daily_data);
+    }
+
+    Ok(LoadReport { stations, errors })
+}
+
+#[cfg(feature = "postgres")]
+fn database_error(error: postgres::Error) -> ClimateError {
+    ClimateError::Database(error.to_string())
+}
+
+fn with_units(value: Option<f64>, mapping: &Option<ColumnMapping>) -> Option<(f64, String)> {
+    match (value, mapping) {
+        (Some(value), Some(mapping)) => Some((value, mapping.units.clone())),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct MemorySource {
+        stations: Vec<StationRow>,
+        daily: Vec<DailyRow>,
+    }
+
+    impl ClimateSource for MemorySource {
+        fn fetch_stations(
+            &mut self,
+            _mapping: &TableMapping,
+            ids: &[i32],
+        ) -> Result<Vec<StationRow>, ClimateError> {
+            Ok(self
+                .stations
+                .iter()
+                .filter(|s| ids.contains(&s.id))
+                .cloned()
+                .collect())
+        }
+
+        // returns every station's rows, like a query that ignores the ids
+        fn fetch_daily(
+            &mut self,
+            _mapping: &TableMapping,
+            _ids: &[i32],
+            start: NaiveDate,
+            end: NaiveDate,
+        ) -> Result<Vec<DailyRow>, ClimateError> {
+            Ok(self
+                .daily
+                .iter()
+                .filter(|d| d.date >= start && d.date <= end)
+                .cloned()
+                .collect())
+        }
+    }
+
+    fn daily_row(station_id: i32, day: u32, tmin: Option<f64>) -> DailyRow {
+        DailyRow {
+            station_id,
+            date: NaiveDate::from_ymd_opt(2023, 7, day).unwrap(),
+            tmin,
+            tmax: Some(30.0),
+            rhmin: Some(25.0),
+            rhmax: Some(80.0),
+            dewpoint: None,
+            precip: Some(0.0),
+            rs: Some(28.0),
+            ea: None,
+            wind_speed: Some(2.5),
+            pressure: Some(29.1),
+        }
+    }
+
+    fn memory_source() -> MemorySource {
+        MemorySource {
+            stations: vec![
+                StationRow {
+                    id: 1,
+                    name: "Mead".to_string(),
+                    source: "test".to_string(),
+                    latitude: 41.15,
+                    longitude: -96.49,
+                    elevation: 366.0,
+                    wind_height: 3.0,
+                },
+                StationRow {
+                    id: 2,
+                    name: "Clay Center".to_string(),
+                    source: "test".to_string(),
+                    latitude: 40.57,
+                    longitude: -98.13,
+                    elevation: 552.0,
+                    wind_height: 2.0,
+                },
+            ],
+            daily: vec![
+                daily_row(1, 1, Some(15.0)),
+                daily_row(1, 2, None),
+                daily_row(1, 3, Some(16.0)),
+                daily_row(2, 1, Some(14.0)),
+                daily_row(2, 9, Some(14.0)),
+            ],
+        }
+    }
+
+    #[test]
+    fn load_stations_from_source() {
+        let mut source = memory_source();
+        let start = NaiveDate::from_ymd_opt(2023, 7, 1).unwrap();
+        let end = NaiveDate::from_ymd_opt(2023, 7, 5).unwrap();
+
+        let report = load_stations(
+            &mut source,
+            &TableMapping::default(),
+            &[2, 1, 2],
+            start,
+            end,
+        )
+        .unwrap();
+        let stations = report.stations;
+
+        // the repeated id is loaded once
+        assert_eq!(stations.len(), 2);
+        assert_eq!(stations[0].name, "Clay Center");
+        assert_eq!(stations[0].daily_data.len(), 1);
+        assert_eq!(stations[1].id, Some(1));
+        assert_eq!(stations[1].wind_height, 3.0);
+        // the day without tmin is skipped and reported
+        assert_eq!(stations[1].daily_data.len(), 2);
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(
+            report.errors[0].to_string(),
+            "Station 1: tmin is required for tmin on 2023-07-02"
+        );
+        assert_eq!(stations[1].daily_data[1].tmin, 16.0);
+        assert_eq!(
+            stations[1].daily_data[0].rs_units,
+            Some("MJ/m²".to_string())
+        );
+        assert_eq!(stations[1].daily_data[0].ea, None);
+        // pressure isn't mapped by default
+        assert_eq!(stations[1].daily_data[0].pressure, None);
+
+        let mapping = TableMapping {
+            pressure: Some(ColumnMapping::new("pressure", "inHg")),
+            ..TableMapping::default()
+        };
+        let report = load_stations(&mut source, &mapping, &[1], start, end).unwrap();
+        let output = report.stations[0].to_output().unwrap();
+        assert!((output[0].get_pressure().unwrap() - 98.54).abs() < 0.01);
+        // station 2 wasn't asked for, its row is reported rather than dropped
+        assert_eq!(report.errors.len(), 2);
+        assert_eq!(
+            report.errors[1].to_string(),
+            "Station 2: Daily row on 2023-07-01 for a station that wasn't requested"
+        );
+
+        assert!(load_station(&mut source, &TableMapping::default(), 3, start, end).is_err());
+    }
+
+    #[test]
+    fn errors_name_the_station() {
+        let mapping = TableMapping {
+            rs: Some(ColumnMapping::new("rs", "")),
+            ..TableMapping::default()
+        };
+        let day = NaiveDate::from_ymd_opt(2023, 7, 1).unwrap();
+
+        let error = load_stations(&mut memory_source(), &mapping, &[2], day, day).unwrap_err();
+        assert_eq!(error.variable(), Some("rs"));
+        assert_eq!(error.date(), Some(day));
+        assert_eq!(
+            error.to_string(),
+            "Station 2: Units must not be empty when including a value for rs on 2023-07-01"
+        );
+    }
+
+    #[test]
+    fn query_uses_mapping() {
+        let mapping = TableMapping {
+            daily_table: "climate.daily".to_string(),
+            tmax: ColumnMapping::new("max_temp", "°F"),
+            ea: None,
+            ..TableMapping::default()
+        };
+
+        let query = mapping.daily_query();
+        assert!(query.contains("FROM climate.daily"));
+        assert!(query.contains("max_temp::float8"));
+        assert!(query.contains("rs::float8, NULL::float8, wind_speed::float8, NULL::float8"));
+    }
+
+    // Runs against a live database when CLIMATE_TEST_DATABASE_URL is set, e.g.
+    // `host=localhost user=postgres dbname=climate_test`.
+    #[cfg(feature = "postgres")]
+    #[test]
+    fn load_station_from_postgres() {
+        let url = match std::env::var("CLIMATE_TEST_DATABASE_URL") {
+            Ok(url) => url,
+            Err(_) => return,
+        };
+
+        let mut client = postgres::Client::connect(&url, postgres::NoTls).unwrap();
+        client
+            .batch_execute(
+                "CREATE TEMP TABLE stations (id integer, name text, source text, \
+                 latitude real, longitude real, elevation numeric, wind_height real);
+                 CREATE TEMP TABLE daily_data (station_id integer, date date, tmin real, \
+                 tmax real, rhmin real, rhmax real, dewpoint real, precip real, rs real, \
+                 ea real, wind_speed real);
+                 INSERT INTO stations VALUES (7, 'Mead', 'test', 41.15, -96.49, 366, NULL);
+                 INSERT INTO daily_data VALUES
+                    (7, '2023-07-01', 15, 30, 25, 80, NULL, 0, 28, NULL, 2.5),
+                    (7, '2023-07-02', 16, 31, 20, 75, NULL, 12.5, 22, NULL, 3.5);",
+            )
+            .unwrap();
+
+        let start = NaiveDate::from_ymd_opt(2023, 7, 2).unwrap();
+        let report = load_station(&mut client, &TableMapping::default(), 7, start, start).unwrap();
+        assert!(report.errors.is_empty());
+        let station = &report.stations[0];
+
+        assert_eq!(station.name, "Mead");
+        assert_eq!(station.elevation, 366.0);
+        assert_eq!(station.wind_height, 2.0);
+        assert_eq!(station.daily_data.len(), 1);
+        assert_eq!(station.daily_data[0].precip, Some(12.5));
+    }
+}
@@ -0,0 +1,215 @@
+//! ASCE-EWRI (2005) standardized reference evapotranspiration equations shared by the QC checks
+//! and the refet calculations. Equation numbers refer to the ASCE-EWRI report.
+
+use std::f64::consts::PI;
+
+/// Solar constant in MJ/m²/h
+pub const SOLAR_CONSTANT: f64 = 4.92;
+
+/// Inverse relative distance factor for the earth-sun distance (Eq. 23).
+pub fn inverse_relative_distance(day_of_year: u32) -> f64 {
+    1.0 + 0.033 * (2.0 * PI / 365.0 * day_of_year as f64).cos()
+}
+
+/// Solar declination in radians (Eq. 24).
+pub fn solar_declination(day_of_year: u32) -> f64 {
+    0.409 * (2.0 * PI / 365.0 * day_of_year as f64 - 1.39).sin()
+}
+
+/// Sunset hour angle in radians (Eq. 27), the argument is clamped so polar day and night don't
+/// produce NaN.
+pub fn sunset_hour_angle(latitude: f64, declination: f64) -> f64 {
+    (-latitude.tan() * declination.tan())
+        .clamp(-1.0, 1.0)
+        .acos()
+}
+
+/// Daily extraterrestrial radiation Ra in MJ/m²/day (Eq. 21) for a latitude in radians.
+pub fn extraterrestrial_radiation(latitude: f64, day_of_year: u32) -> f64 {
+    let dr = inverse_relative_distance(day_of_year);
+    let declination = solar_declination(day_of_year);
+    let ws = sunset_hour_angle(latitude, declination);
+
+    24.0 / PI
+        * SOLAR_CONSTANT
+        * dr
+        * (ws * latitude.sin() * declination.sin() + latitude.cos() * declination.cos() * ws.sin())
+}
+
+/// Seasonal correction for solar time in hours (Eq. 57).
+pub fn seasonal_correction(day_of_year: u32) -> f64 {
+    let b = 2.0 * PI * (day_of_year as f64 - 81.0) / 364.0;
+    0.1645 * (2.0 * b).sin() - 0.1255 * b.cos() - 0.025 * b.sin()
+}
+
+/// Solar time angle in radians at the midpoint of a period (Eq. 55). `standard_time` is the
+/// standard clock time of the midpoint in hours, e.g. 14.5 for 14:00 to 15:00, and the longitudes
+/// are in degrees positive east.
+pub fn solar_time_angle(
+    standard_time: f64,
+    longitude: f64,
+    standard_meridian: f64,
+    day_of_year: u32,
+) -> f64 {
+    PI / 12.0
+        * (standard_time
+            + (longitude - standard_meridian) / 15.0
+            + seasonal_correction(day_of_year)
+            - 12.0)
+}
+
+/// Sine of the sun angle above the horizon for a solar time angle in radians (Eq. 62).
+pub fn sin_sun_angle(latitude: f64, day_of_year: u32, solar_time_angle: f64) -> f64 {
+    let declination = solar_declination(day_of_year);
+    latitude.sin() * declination.sin() + latitude.cos() * declination.cos() * solar_time_angle.cos()
+}
+
+/// Hourly extraterrestrial radiation Ra in MJ/m²/h (Eq. 48) for the hour centered on a solar
+/// time angle, with the period limited to the hours the sun is up (Eq. 56).
+pub fn hourly_extraterrestrial_radiation(
+    latitude: f64,
+    day_of_year: u32,
+    solar_time_angle: f64,
+) -> f64 {
+    let dr = inverse_relative_distance(day_of_year);
+    let declination = solar_declination(day_of_year);
+    let ws = sunset_hour_angle(latitude, declination);
+    let w2 = (solar_time_angle + PI / 24.0).clamp(-ws, ws);
+    let w1 = (solar_time_angle - PI / 24.0).clamp(-ws, ws).min(w2);
+
+    (12.0 / PI
+        * SOLAR_CONSTANT
+        * dr
+        * ((w2 - w1) * latitude.sin() * declination.sin()
+            + latitude.cos() * declination.cos() * (w2.sin() - w1.sin())))
+    .max(0.0)
+}
+
+/// Clear-sky solar radiation Rso in the same units as `ra` (Eq. 19), using the station elevation
+/// in meters.
+pub fn clear_sky_radiation(ra: f64, elevation: f64) -> f64 {
+    (0.75 + 2e-5 * elevation) * ra
+}
+
+/// Saturation vapor pressure in kPa at a temperature in Celsius (Eq. 7).
+pub fn saturation_vapor_pressure(temperature: f64) -> f64 {
+    0.6108 * (17.27 * temperature / (temperature + 237.3)).exp()
+}
+
+/// Actual vapor pressure in kPa from the daily relative humidity extremes (Eq. 11).
+pub fn vapor_pressure_from_rh(tmin: f64, tmax: f64, rhmin: f64, rhmax: f64) -> f64 {
+    (saturation_vapor_pressure(tmin) * rhmax / 100.0
+        + saturation_vapor_pressure(tmax) * rhmin / 100.0)
+        / 2.0
+}
+
+/// Dewpoint temperature in Celsius for an actual vapor pressure in kPa, the inverse of Eq. 7.
+pub fn dewpoint_from_vapor_pressure(ea: f64) -> f64 {
+    let ln_ea = (ea / 0.6108).ln();
+    237.3 * ln_ea / (17.27 - ln_ea)
+}
+
+/// Mean atmospheric pressure in kPa at an elevation in meters (Eq. 3).
+pub fn atmospheric_pressure(elevation: f64) -> f64 {
+    101.3 * ((293.0 - 0.0065 * elevation) / 293.0).powf(5.26)
+}
+
+/// Psychrometric constant in kPa/°C for an atmospheric pressure in kPa (Eq. 4).
+pub fn psychrometric_constant(pressure: f64) -> f64 {
+    0.000665 * pressure
+}
+
+/// Slope of the saturation vapor pressure-temperature curve in kPa/°C (Eq. 5).
+pub fn saturation_slope(temperature: f64) -> f64 {
+    2503.0 * (17.27 * temperature / (temperature + 237.3)).exp() / (temperature + 237.3).powi(2)
+}
+
+/// Cloudiness function fcd from the relative shortwave radiation Rs/Rso (Eq. 18), the ratio is
+/// limited to 0.3 - 1.0.
+pub fn cloudiness_function(rs: f64, rso: f64) -> f64 {
+    let ratio = if rso > 0.0 {
+        (rs / rso).clamp(0.3, 1.0)
+    } else {
+        1.0
+    };
+    1.35 * ratio - 0.35
+}
+
+/// Daily net long-wave radiation in MJ/m²/day (Eq. 17).
+pub fn net_longwave_radiation(tmin: f64, tmax: f64, ea: f64, fcd: f64) -> f64 {
+    const STEFAN_BOLTZMANN: f64 = 4.901e-9;
+    STEFAN_BOLTZMANN
+        * fcd
+        * (0.34 - 0.14 * ea.sqrt())
+        * ((tmax + 273.16).powi(4) + (tmin + 273.16).powi(4))
+        / 2.0
+}
+
+/// Hourly net long-wave radiation in MJ/m²/h (Eq. 44) for the mean hourly temperature.
+pub fn hourly_net_longwave_radiation(temperature: f64, ea: f64, fcd: f64) -> f64 {
+    const STEFAN_BOLTZMANN: f64 = 2.042e-10;
+    STEFAN_BOLTZMANN * fcd * (0.34 - 0.14 * ea.sqrt()) * (temperature + 273.16).powi(4)
+}
+
+/// Wind speed at 2 m from a speed measured at `height` meters using the log profile (Eq. 33).
+pub fn wind_speed_at_2m(wind_speed: f64, height: f64) -> f64 {
+    if height == 2.0 {
+        return wind_speed;
+    }
+    wind_speed * 4.87 / (67.8 * height - 5.42).ln()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn daily_radiation_terms() {
+        // FAO-56 Example 8, 20°S on September 3rd
+        let latitude = -20.0_f64.to_radians();
+        let ra = extraterrestrial_radiation(latitude, 246);
+        assert_eq!((ra * 10.0).round() / 10.0, 32.2);
+
+        let rso = clear_sky_radiation(ra, 0.0);
+        assert_eq!((rso * 10.0).round() / 10.0, 24.1);
+    }
+
+    #[test]
+    fn hourly_radiation_terms() {
+        // FAO-56 Example 19, N'Diaye at 16°13'N and 16°15'W on October 1st from 14:00 to 15:00
+        let omega = solar_time_angle(14.5, -16.25, -15.0, 274);
+        assert_eq!((omega * 1000.0).round() / 1000.0, 0.682);
+
+        let ra = hourly_extraterrestrial_radiation(16.217_f64.to_radians(), 274, omega);
+        assert_eq!((ra * 100.0).round() / 100.0, 3.54);
+        assert!(sin_sun_angle(16.217_f64.to_radians(), 274, omega) > 0.3_f64.sin());
+        assert_eq!(hourly_extraterrestrial_radiation(0.7, 274, PI), 0.0);
+    }
+
+    #[test]
+    fn atmospheric_terms() {
+        // FAO-56 Examples 2 and 17
+        let pressure = atmospheric_pressure(1800.0);
+        assert_eq!((pressure * 10.0).round() / 10.0, 81.8);
+        assert_eq!(
+            (psychrometric_constant(pressure) * 1000.0).round() / 1000.0,
+            0.054
+        );
+        assert_eq!((saturation_slope(16.9) * 1000.0).round() / 1000.0, 0.122);
+        assert_eq!((wind_speed_at_2m(3.2, 10.0) * 10.0).round() / 10.0, 2.4);
+    }
+
+    #[test]
+    fn vapor_pressure_terms() {
+        assert_eq!(
+            (saturation_vapor_pressure(25.0) * 1000.0).round() / 1000.0,
+            3.168
+        );
+        let dewpoint = dewpoint_from_vapor_pressure(saturation_vapor_pressure(12.5));
+        assert!((dewpoint - 12.5).abs() < 1e-9);
+
+        // FAO-56 Example 5
+        let ea = vapor_pressure_from_rh(18.0, 25.0, 54.0, 82.0);
+        assert_eq!((ea * 100.0).round() / 100.0, 1.70);
+    }
+}
@@ -0,0 +1,273 @@
+use chrono::NaiveDate;
+use std::fmt;
+
+/// The error type for the crate. Every variant carries the variable and date it applies to when
+/// they are known, so a bad row can be reported and skipped instead of stopping a batch job.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClimateError {
+    /// the unit string could not be matched to a `Units` value
+    UnknownUnit {
+        unit: String,
+        /// the closest known unit, when one is close enough to be a likely typo
+        suggestion: Option<String>,
+        variable: Option<String>,
+        date: Option<NaiveDate>,
+    },
+    /// the units are known but can't be converted between, or aren't valid for the variable
+    UnsupportedConversion {
+        from: String,
+        to: String,
+        variable: Option<String>,
+        date: Option<NaiveDate>,
+    },
+    /// the units measure different dimensions, such as a temperature and a length
+    IncompatibleDimensions {
+        from: String,
+        from_dimension: String,
+        to: String,
+        to_dimension: String,
+        variable: Option<String>,
+        date: Option<NaiveDate>,
+    },
+    /// a value was given without the units it is in
+    MissingUnits {
+        variable: Option<String>,
+        date: Option<NaiveDate>,
+    },
+    /// the value can't be used, the message says why
+    InvalidValue {
+        message: String,
+        variable: Option<String>,
+        date: Option<NaiveDate>,
+    },
+    /// a line of an input file could not be read, `row` is the line number in the file
+    Parse {
+        message: String,
+        row: Option<usize>,
+        column: Option<String>,
+    },
+    /// the database query or connection failed
+    Database(String),
+    /// a file could not be opened or read
+    Io(String),
+    /// an error in the records of one station, for loads that read many stations at once
+    Station {
+        station: String,
+        error: Box<ClimateError>,
+    },
+}
+
+impl ClimateError {
+    pub fn unknown_unit(unit: &str) -> ClimateError {
+        ClimateError::UnknownUnit {
+            unit: unit.to_string(),
+            suggestion: None,
+            variable: None,
+            date: None,
+        }
+    }
+
+    pub fn unsupported_conversion(from: &str, to: &str) -> ClimateError {
+        ClimateError::UnsupportedConversion {
+            from: from.to_string(),
+            to: to.to_string(),
+            variable: None,
+            date: None,
+        }
+    }
+
+    pub fn incompatible_dimensions(
+        from: &str,
+        from_dimension: &str,
+        to: &str,
+        to_dimension: &str,
+    ) -> ClimateError {
+        ClimateError::IncompatibleDimensions {
+            from: from.to_string(),
+            from_dimension: from_dimension.to_string(),
+            to: to.to_string(),
+            to_dimension: to_dimension.to_string(),
+            variable: None,
+            date: None,
+        }
+    }
+
+    pub fn missing_units(variable: &str, date: NaiveDate) -> ClimateError {
+        ClimateError::MissingUnits {
+            variable: Some(variable.to_string()),
+            date: Some(date),
+        }
+    }
+
+    pub fn invalid_value(message: String) -> ClimateError {
+        ClimateError::InvalidValue {
+            message,
+            variable: None,
+            date: None,
+        }
+    }
+
+    pub fn parse(message: String, row: Option<usize>, column: Option<&str>) -> ClimateError {
+        ClimateError::Parse {
+            message,
+            row,
+            column: column.map(|column| column.to_string()),
+        }
+    }
+
+    /// Names the station the error was found in.
+    pub fn for_station(self, station: &str) -> ClimateError {
+        ClimateError::Station {
+            station: station.to_string(),
+            error: Box::new(self),
+        }
+    }
+
+    /// Fills in the variable and date of the error when they aren't already set.
+    pub fn with_context(self, variable_name: &str, on: NaiveDate) -> ClimateError {
+        if let ClimateError::Station { station, error } = self {
+            return error.with_context(variable_name, on).for_station(&station);
+        }
+        let mut error = self.with_variable(variable_name);
+        if let ClimateError::UnknownUnit { date, .. }
+        | ClimateError::UnsupportedConversion { date, .. }
+        | ClimateError::IncompatibleDimensions { date, .. }
+        | ClimateError::MissingUnits { date, .. }
+        | ClimateError::InvalidValue { date, .. } = &mut error
+        {
+            date.get_or_insert(on);
+        }
+        error
+    }
+
+    /// Fills in the variable of the error when it isn't already set, for errors not tied to a day.
+    pub fn with_variable(mut self, variable_name: &str) -> ClimateError {
+        if let ClimateError::Station { station, error } = self {
+            return error.with_variable(variable_name).for_station(&station);
+        }
+        match &mut self {
+            ClimateError::UnknownUnit { variable, .. }
+            | ClimateError::UnsupportedConversion { variable, .. }
+            | ClimateError::IncompatibleDimensions { variable, .. }
+            | ClimateError::MissingUnits { variable, .. }
+            | ClimateError::InvalidValue { variable, .. } => {
+                variable.get_or_insert_with(|| variable_name.to_string());
+            }
+            ClimateError::Parse { .. }
+            | ClimateError::Database(_)
+            | ClimateError::Io(_)
+            | ClimateError::Station { .. } => {}
+        }
+        self
+    }
+
+    pub fn variable(&self) -> Option<&str> {
+        match self {
+            ClimateError::UnknownUnit { variable, .. }
+            | ClimateError::UnsupportedConversion { variable, .. }
+            | ClimateError::IncompatibleDimensions { variable, .. }
+            | ClimateError::MissingUnits { variable, .. }
+            | ClimateError::InvalidValue { variable, .. } => variable.as_deref(),
+            ClimateError::Station { error, .. } => error.variable(),
+            ClimateError::Parse { .. } | ClimateError::Database(_) | ClimateError::Io(_) => None,
+        }
+    }
+
+    pub fn date(&self) -> Option<NaiveDate> {
+        match self {
+            ClimateError::UnknownUnit { date, .. }
+            | ClimateError::UnsupportedConversion { date, .. }
+            | ClimateError::IncompatibleDimensions { date, .. }
+            | ClimateError::MissingUnits { date, .. }
+            | ClimateError::InvalidValue { date, .. } => *date,
+            ClimateError::Station { error, .. } => error.date(),
+            ClimateError::Parse { .. } | ClimateError::Database(_) | ClimateError::Io(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for ClimateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClimateError::UnknownUnit {
+                unit, suggestion, ..
+            } => {
+                write!(f, "Invalid unit: {}", unit)?;
+                if let Some(suggestion) = suggestion {
+                    write!(f, " (did you mean {}?)", suggestion)?;
+                }
+            }
+            ClimateError::UnsupportedConversion { from, to, .. } => {
+                write!(f, "Unsupported conversion from {} to {}", from, to)?
+            }
+            ClimateError::IncompatibleDimensions {
+                from,
+                from_dimension,
+                to,
+                to_dimension,
+                ..
+            } => write!(
+                f,
+                "Can't convert {}, a {}, to {}, a {}",
+                from, from_dimension, to, to_dimension
+            )?,
+            ClimateError::MissingUnits { .. } => {
+                write!(f, "Units must not be empty when including a value")?
+            }
+            ClimateError::InvalidValue { message, .. } => write!(f, "{}", message)?,
+            ClimateError::Parse {
+                message,
+                row,
+                column,
+            } => {
+                write!(f, "{}", message)?;
+                if let Some(row) = row {
+                    write!(f, " at row {}", row)?;
+                }
+                if let Some(column) = column {
+                    write!(f, " in column {}", column)?;
+                }
+                return Ok(());
+            }
+            ClimateError::Database(message) => return write!(f, "Database error: {}", message),
+            ClimateError::Io(message) => return write!(f, "IO error: {}", message),
+            ClimateError::Station { station, error } => {
+                return write!(f, "Station {}: {}", station, error)
+            }
+        }
+
+        if let Some(variable) = self.variable() {
+            write!(f, " for {}", variable)?;
+        }
+        if let Some(date) = self.date() {
+            write!(f, " on {}", date)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ClimateError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn context_is_added_once() {
+        let date = NaiveDate::from_ymd_opt(2023, 7, 1).unwrap();
+        let error = ClimateError::unknown_unit("furlongs")
+            .with_context("wind_speed", date)
+            .with_context("tmin", NaiveDate::from_ymd_opt(2024, 1, 1).unwrap());
+
+        assert_eq!(error.variable(), Some("wind_speed"));
+        assert_eq!(error.date(), Some(date));
+        assert_eq!(
+            error.to_string(),
+            "Invalid unit: furlongs for wind_speed on 2023-07-01"
+        );
+        assert_eq!(
+            ClimateError::Database("timeout".to_string()).to_string(),
+            "Database error: timeout"
+        );
+    }
+}
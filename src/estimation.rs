@@ -0,0 +1,176 @@
+//! Estimates for missing rs, humidity and wind speed following the ASCE-EWRI (2005) Appendix E
+//! guidance, so a refet run isn't stopped by a single missing sensor.
+
+use crate::data_model::Variable;
+use crate::equations::{clear_sky_radiation, extraterrestrial_radiation};
+use crate::output::Output;
+use crate::quantity::{TemperatureDifference, TemperatureDifferenceUnits};
+use chrono::Datelike;
+
+/// How an estimated value was produced.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EstimationMethod {
+    /// Rs from the Hargreaves-Samani temperature difference method
+    HargreavesSamani,
+    /// dewpoint from Tmin less an offset
+    DewpointFromTmin,
+    /// wind speed from a default for the station
+    StationDefault,
+    /// wind speed from a default for the region
+    RegionalDefault,
+}
+
+impl EstimationMethod {
+    pub fn name(&self) -> &str {
+        match self {
+            EstimationMethod::HargreavesSamani => "Hargreaves-Samani",
+            EstimationMethod::DewpointFromTmin => "Dewpoint from Tmin",
+            EstimationMethod::StationDefault => "Station Default",
+            EstimationMethod::RegionalDefault => "Regional Default",
+        }
+    }
+}
+
+/// Records that a value in an `Output` was estimated rather than measured.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Estimate {
+    pub variable: Variable,
+    pub method: EstimationMethod,
+}
+
+/// Settings for the estimates.
+#[derive(Debug, Clone)]
+pub struct EstimationConfig {
+    /// Hargreaves-Samani adjustment coefficient, 0.16 for interior and 0.19 for coastal locations
+    pub krs: f64,
+    /// Tmin - Tdew, 0 for humid and sub-humid climates and 2 to 4 °C for arid ones
+    pub dewpoint_offset: TemperatureDifference,
+    /// wind speed at 2 m in m/s for the station, used before the regional default when set
+    pub station_wind_speed: Option<f64>,
+    /// wind speed at 2 m in m/s for the region
+    pub regional_wind_speed: f64,
+}
+
+impl Default for EstimationConfig {
+    fn default() -> Self {
+        EstimationConfig {
+            krs: 0.16,
+            dewpoint_offset: TemperatureDifference::new(0.0, TemperatureDifferenceUnits::Celsius),
+            station_wind_speed: None,
+            regional_wind_speed: 2.0,
+        }
+    }
+}
+
+/// Fills missing rs, humidity and wind speed in each output and marks every filled value as
+/// estimated with the method used. Humidity is only estimated when ea, dewpoint and rhmax are all
+/// missing. Returns the number of values that were filled.
+pub fn estimate_missing(outputs: &mut [Output], config: &EstimationConfig) -> usize {
+    let mut filled = 0;
+    for output in outputs.iter_mut() {
+        if output.get_rs().is_none() {
+            output.set_rs(Some(hargreaves_samani_rs(output, config.krs)));
+            output.add_estimate(Variable::Rs, EstimationMethod::HargreavesSamani);
+            filled += 1;
+        }
+
+        if output.get_ea().is_none()
+            && output.get_dewpoint().is_none()
+            && output.get_rhmax().is_none()
+        {
+            output.set_dewpoint(Some(output.get_tmin() - config.dewpoint_offset.celsius()));
+            output.add_estimate(Variable::Dewpoint, EstimationMethod::DewpointFromTmin);
+            filled += 1;
+        }
+
+        if output.get_ws().is_none() {
+            let (u2, method) = match config.station_wind_speed {
+                Some(u2) => (u2, EstimationMethod::StationDefault),
+                None => (
+                    config.regional_wind_speed,
+                    EstimationMethod::RegionalDefault,
+                ),
+            };
+            // the defaults are 2 m values, like the wind speed `StationData::to_output` gives
+            output.set_ws(Some(u2));
+            output.set_wz(Some(2.0));
+            output.add_estimate(Variable::WindSpeed, method);
+            filled += 1;
+        }
+    }
+
+    filled
+}
+
+/// Rs in MJ/m²/day from the daily temperature range (Eq. E.1), limited to clear-sky radiation.
+pub fn hargreaves_samani_rs(output: &Output, krs: f64) -> f64 {
+    let ra = extraterrestrial_radiation(output.get_latitude(), output.get_date().ordinal());
+    let rso = clear_sky_radiation(ra, output.get_z());
+    let range = (output.get_tmax() - output.get_tmin()).max(0.0);
+    (krs * range.sqrt() * ra).min(rso)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn output() -> Output {
+        let mut output = Output::new();
+        output.set_date(NaiveDate::from_ymd_opt(2023, 7, 15).unwrap());
+        output.set_tmin(14.8);
+        output.set_tmax(26.6);
+        output.set_z(200.0);
+        output.set_latitude(45.72);
+        output.set_wz(Some(3.0));
+        output
+    }
+
+    #[test]
+    fn fills_and_marks_missing_values() {
+        let mut measured = output();
+        measured.set_rs(Some(25.0));
+        measured.set_rhmax(Some(80.0));
+        measured.set_ws(Some(3.0));
+        let mut outputs = vec![output(), measured];
+
+        let config = EstimationConfig {
+            dewpoint_offset: TemperatureDifference::new(
+                3.6,
+                TemperatureDifferenceUnits::Fahrenheit,
+            ),
+            station_wind_speed: Some(1.8),
+            ..EstimationConfig::default()
+        };
+        assert_eq!(estimate_missing(&mut outputs, &config), 3);
+
+        let estimated = &outputs[0];
+        let ra = extraterrestrial_radiation(45.72_f64.to_radians(), 196);
+        assert_eq!(estimated.get_rs(), Some(0.16 * 11.8_f64.sqrt() * ra));
+        assert_eq!(estimated.get_dewpoint(), Some(12.8));
+        assert_eq!(estimated.get_ws(), Some(1.8));
+        assert_eq!(estimated.get_wz(), 2.0);
+        assert_eq!(
+            estimated.get_estimates(),
+            &[
+                Estimate {
+                    variable: Variable::Rs,
+                    method: EstimationMethod::HargreavesSamani
+                },
+                Estimate {
+                    variable: Variable::Dewpoint,
+                    method: EstimationMethod::DewpointFromTmin
+                },
+                Estimate {
+                    variable: Variable::WindSpeed,
+                    method: EstimationMethod::StationDefault
+                },
+            ]
+        );
+
+        assert!(outputs[1].get_estimates().is_empty());
+        assert_eq!(outputs[1].get_dewpoint(), None);
+    }
+}
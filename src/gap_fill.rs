@@ -0,0 +1,367 @@
+//! Fills gaps in a station from its neighbors using per-variable, per-month linear regressions fit
+//! over the days both stations have data.
+
+use crate::data_model::{DailyData, StationData, Variable};
+use crate::error::ClimateError;
+use chrono::{Datelike, Duration, NaiveDate};
+use std::collections::HashMap;
+
+/// How a filled value was produced.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FillMethod {
+    /// target = intercept + slope * donor, fit for the calendar month of the filled day
+    MonthlyLinearRegression,
+}
+
+/// The provenance of a filled value, kept on the `DailyData` it was written to.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct GapFill {
+    pub variable: Variable,
+    pub donor_name: String,
+    pub donor_id: Option<i32>,
+    pub r_squared: f64,
+    pub method: FillMethod,
+}
+
+/// Settings for the gap filling.
+#[derive(Debug, Clone)]
+pub struct GapFillConfig {
+    /// the variables to fill
+    pub variables: Vec<Variable>,
+    /// fewest overlapping days in a month needed to fit a regression
+    pub min_overlap: usize,
+    /// fits with a coefficient of determination below this are not used
+    pub min_r_squared: f64,
+    /// neighbors farther away than this are not used as donors
+    pub max_distance_km: Option<f64>,
+    /// first and last day to fill, defaults to the first and last `DailyData` of the target
+    pub period: Option<(NaiveDate, NaiveDate)>,
+    /// create `DailyData` records for days the target has no record for at all
+    pub fill_missing_records: bool,
+}
+
+impl Default for GapFillConfig {
+    fn default() -> Self {
+        GapFillConfig {
+            variables: Variable::all().to_vec(),
+            min_overlap: 10,
+            min_r_squared: 0.5,
+            max_distance_km: None,
+            period: None,
+            fill_missing_records: true,
+        }
+    }
+}
+
+/// A fitted relationship between the target and one neighbor for a variable and month.
+#[derive(Debug, Clone)]
+struct Fit {
+    donor: usize,
+    intercept: f64,
+    slope: f64,
+    r_squared: f64,
+}
+
+/// Fills missing values, and optionally whole missing `DailyData` records, in `target` from the
+/// `neighbors`. For each variable and calendar month a regression is fit against every neighbor
+/// over their overlapping days; a missing day is filled from the neighbor with the highest R²
+/// that has a value that day. Filled values are written in the `Output` units and each one records
+/// its `GapFill` provenance. QC flagged values are not used to fit or fill.
+///
+/// A missing record is only created when both tmin and tmax can be filled. Returns the number of
+/// values filled.
+pub fn fill_gaps(
+    target: &mut StationData,
+    neighbors: &[StationData],
+    config: &GapFillConfig,
+) -> Result<usize, ClimateError> {
+    let donors: Vec<&StationData> = neighbors
+        .iter()
+        .filter(|n| match config.max_distance_km {
+            Some(max_distance) => target.distance_to(n) <= max_distance,
+            None => true,
+        })
+        .collect();
+
+    let mut donor_values = Vec::with_capacity(donors.len());
+    for donor in &donors {
+        donor_values.push(series(donor, &config.variables)?);
+    }
+    let target_values = series(target, &config.variables)?;
+
+    // fits for each variable and month, best first
+    let mut fits: HashMap<(Variable, u32), Vec<Fit>> = HashMap::new();
+    for variable in &config.variables {
+        for month in 1..=12 {
+            let mut month_fits: Vec<Fit> = donor_values
+                .iter()
+                .enumerate()
+                .filter_map(|(donor, values)| {
+                    let pairs: Vec<(f64, f64)> = target_values[variable]
+                        .iter()
+                        .filter(|(date, _)| date.month() == month)
+                        .filter_map(|(date, y)| values[variable].get(date).map(|x| (*x, *y)))
+                        .collect();
+                    if pairs.len() < config.min_overlap {
+                        return None;
+                    }
+                    regression(&pairs).map(|(intercept, slope, r_squared)| Fit {
+                        donor,
+                        intercept,
+                        slope,
+                        r_squared,
+                    })
+                })
+                .filter(|fit| fit.r_squared >= config.min_r_squared)
+                .collect();
+            month_fits.sort_by(|a, b| b.r_squared.total_cmp(&a.r_squared));
+            fits.insert((*variable, month), month_fits);
+        }
+    }
+
+    let estimate = |variable: Variable, date: NaiveDate| -> Option<(f64, GapFill)> {
+        fits.get(&(variable, date.month()))?.iter().find_map(|fit| {
+            let x = donor_values[fit.donor][&variable].get(&date)?;
+            let donor = donors[fit.donor];
+            Some((
+                limit(variable, fit.intercept + fit.slope * x),
+                GapFill {
+                    variable,
+                    donor_name: donor.name.clone(),
+                    donor_id: donor.id,
+                    r_squared: fit.r_squared,
+                    method: FillMethod::MonthlyLinearRegression,
+                },
+            ))
+        })
+    };
+
+    let mut filled = 0;
+    let (start, end) = match config.period {
+        Some(period) => period,
+        None => match (
+            target.daily_data.iter().map(|d| d.date).min(),
+            target.daily_data.iter().map(|d| d.date).max(),
+        ) {
+            (Some(start), Some(end)) => (start, end),
+            _ => return Ok(0),
+        },
+    };
+
+    for daily_data in target.daily_data.iter_mut() {
+        if daily_data.date < start || daily_data.date > end {
+            continue;
+        }
+        for variable in &config.variables {
+            // flagged values are kept, only values that are missing are filled
+            if daily_data.value_in_output_units(*variable)?.is_some() {
+                continue;
+            }
+            if let Some((value, gap_fill)) = estimate(*variable, daily_data.date) {
+                daily_data.set_value(*variable, value, units(*variable));
+                daily_data.gap_fills.push(gap_fill);
+                filled += 1;
+            }
+        }
+    }
+
+    if config.fill_missing_records {
+        let existing: Vec<NaiveDate> = target.daily_data.iter().map(|d| d.date).collect();
+        let mut date = start;
+        while date <= end {
+            if !existing.contains(&date) {
+                if let (Some(tmin), Some(tmax)) = (
+                    estimate(Variable::Tmin, date),
+                    estimate(Variable::Tmax, date),
+                ) {
+                    let mut daily_data = DailyData::new(
+                        date,
+                        (tmin.0, units(Variable::Tmin)),
+                        (tmax.0, units(Variable::Tmax)),
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                    )?;
+                    daily_data.gap_fills.push(tmin.1);
+                    daily_data.gap_fills.push(tmax.1);
+                    filled += 2;
+
+                    for variable in &config.variables {
+                        if matches!(variable, Variable::Tmin | Variable::Tmax) {
+                            continue;
+                        }
+                        if let Some((value, gap_fill)) = estimate(*variable, date) {
+                            daily_data.set_value(*variable, value, units(*variable));
+                            daily_data.gap_fills.push(gap_fill);
+                            filled += 1;
+                        }
+                    }
+                    target.daily_data.push(daily_data);
+                }
+            }
+            date += Duration::days(1);
+        }
+        target.daily_data.sort_by_key(|d| d.date);
+    }
+
+    Ok(filled)
+}
+
+/// The unflagged values of each variable by date, in the `Output` units.
+fn series(
+    station: &StationData,
+    variables: &[Variable],
+) -> Result<HashMap<Variable, HashMap<NaiveDate, f64>>, ClimateError> {
+    let mut values: HashMap<Variable, HashMap<NaiveDate, f64>> =
+        variables.iter().map(|v| (*v, HashMap::new())).collect();
+    for daily_data in &station.daily_data {
+        for variable in variables {
+            if daily_data.is_flagged(*variable) {
+                continue;
+            }
+            if let Some(value) = daily_data.value_in_output_units(*variable)? {
+                values
+                    .get_mut(variable)
+                    .unwrap()
+                    .insert(daily_data.date, value);
+            }
+        }
+    }
+    Ok(values)
+}
+
+/// Least squares fit of y on x, returning the intercept, slope and R².
+fn regression(pairs: &[(f64, f64)]) -> Option<(f64, f64, f64)> {
+    let n = pairs.len() as f64;
+    let mean_x = pairs.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = pairs.iter().map(|(_, y)| y).sum::<f64>() / n;
+    let sxx: f64 = pairs.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+    let syy: f64 = pairs.iter().map(|(_, y)| (y - mean_y).powi(2)).sum();
+    let sxy: f64 = pairs.iter().map(|(x, y)| (x - mean_x) * (y - mean_y)).sum();
+    if sxx == 0.0 || syy == 0.0 {
+        return None;
+    }
+
+    let slope = sxy / sxx;
+    Some((mean_y - slope * mean_x, slope, sxy * sxy / (sxx * syy)))
+}
+
+/// Keeps a regression estimate inside the physical range of the variable.
+fn limit(variable: Variable, value: f64) -> f64 {
+    match variable {
+        Variable::RhMin | Variable::RhMax => value.clamp(0.0, 100.0),
+        Variable::Precip
+        | Variable::Rs
+        | Variable::Ea
+        | Variable::WindSpeed
+        | Variable::Pressure => value.max(0.0),
+        Variable::Tmin | Variable::Tmax | Variable::Dewpoint => value,
+    }
+}
+
+fn units(variable: Variable) -> String {
+    variable.output_units().to_abbreviation().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn station(name: &str, id: i32, days: &[(u32, f64, Option<f64>)]) -> StationData {
+        let mut station = StationData::new(
+            name.to_string(),
+            "test".to_string(),
+            41.0,
+            -96.0 - id as f64 * 0.1,
+            350.0,
+            2.0,
+            Some(id),
+        );
+        let records = days
+            .iter()
+            .map(|(day, tmin, ws)| {
+                DailyData::new(
+                    NaiveDate::from_ymd_opt(2023, 7, *day).unwrap(),
+                    (*tmin, "°F".to_string()),
+                    (*tmin + 25.0, "°F".to_string()),
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    ws.map(|ws| (ws, "m/s".to_string())),
+                )
+                .unwrap()
+            })
+            .collect();
+        station.add_daily_records(records);
+        station
+    }
+
+    #[test]
+    fn fills_values_and_records_from_best_neighbor() {
+        let neighbor_days: Vec<(u32, f64, Option<f64>)> = (1..=20)
+            .map(|day| {
+                (
+                    day,
+                    50.0 + (day % 7) as f64 * 2.0,
+                    Some(1.0 + (day % 5) as f64),
+                )
+            })
+            .collect();
+        // the close neighbor tracks the target exactly, the other is noisy
+        let close = station("Close", 1, &neighbor_days);
+        let noisy_days: Vec<(u32, f64, Option<f64>)> = neighbor_days
+            .iter()
+            .map(|(day, tmin, _)| (*day, tmin + (day % 3) as f64 * 4.0, None))
+            .collect();
+        let noisy = station("Noisy", 2, &noisy_days);
+
+        // the target is 2 °F warmer with twice the wind, day 15 has no wind and 16-17 are missing
+        let target_days: Vec<(u32, f64, Option<f64>)> = neighbor_days
+            .iter()
+            .filter(|(day, _, _)| *day != 16 && *day != 17)
+            .map(|(day, tmin, ws)| {
+                let ws = if *day == 15 {
+                    None
+                } else {
+                    ws.map(|ws| ws * 2.0)
+                };
+                (*day, tmin + 2.0, ws)
+            })
+            .collect();
+        let mut target = station("Target", 3, &target_days);
+
+        let filled = fill_gaps(&mut target, &[noisy, close], &GapFillConfig::default()).unwrap();
+        assert_eq!(filled, 7);
+        assert_eq!(target.daily_data.len(), 20);
+
+        let day_15 = &target.daily_data[14];
+        assert_eq!(day_15.ws_units, Some("m/s".to_string()));
+        assert!((day_15.wind_speed.unwrap() - 2.0).abs() < 1e-9);
+        assert_eq!(day_15.gap_fills[0].donor_id, Some(1));
+        assert_eq!(day_15.gap_fills[0].variable, Variable::WindSpeed);
+
+        let day_16 = &target.daily_data[15];
+        assert_eq!(day_16.date, NaiveDate::from_ymd_opt(2023, 7, 16).unwrap());
+        assert_eq!(day_16.tmin_units, "°C");
+        let expected = (50.0 + 2.0 * 2.0 + 2.0 - 32.0) * 5.0 / 9.0;
+        assert!((day_16.tmin - expected).abs() < 1e-9);
+        assert_eq!(day_16.gap_fills.len(), 3);
+        assert!(day_16.gap_fills.iter().all(|f| f.donor_name == "Close"));
+        assert!((day_16.gap_fills[0].r_squared - 1.0).abs() < 1e-9);
+        assert_eq!(
+            day_16.gap_fills[0].method,
+            FillMethod::MonthlyLinearRegression
+        );
+
+        assert!(target.daily_data[0].gap_fills.is_empty());
+    }
+}
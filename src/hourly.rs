@@ -0,0 +1,429 @@
+//! Hourly observations for the hourly form of the ASCE standardized reference ET.
+//!
+//! Hourly timestamps mark the end of the hour in the station's local standard time, the time base
+//! the ASCE hourly equations and most loggers use. Data recorded on the local clock, which moves
+//! an hour for daylight saving time, is moved to standard time with
+//! `StationData::to_standard_time` before it is added.
+
+use crate::data_model::{
+    check_rh_units, convert_ea_to_kpa, convert_precip_to_mm, convert_temp_to_c, convert_ws_to_ms,
+    DailyData, StationData,
+};
+use crate::error::ClimateError;
+use crate::output::HourlyOutput;
+use crate::quantity::{RadiantExposure, RadiantExposureUnits};
+use crate::refet::{hourly_refet, HourlyRefEt};
+use crate::units::Period;
+use chrono::{Duration, LocalResult, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::OffsetComponents;
+use std::collections::BTreeMap;
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug)]
+pub struct HourlyData {
+    /// end of the hour in local standard time
+    pub timestamp: NaiveDateTime,
+    pub temperature: f64,
+    pub temperature_units: String,
+    pub rh: Option<f64>,
+    pub rh_units: Option<String>,
+    pub dewpoint: Option<f64>,
+    pub dewpoint_units: Option<String>,
+    pub precip: Option<f64>,
+    pub precip_units: Option<String>,
+    /// solar radiation for the hour, as a total or as the mean flux in W/m²
+    pub rs: Option<f64>,
+    pub rs_units: Option<String>,
+    pub ea: Option<f64>,
+    pub ea_units: Option<String>,
+    pub wind_speed: Option<f64>,
+    pub ws_units: Option<String>,
+    pub short_refet: Option<f64>,
+    pub tall_refet: Option<f64>,
+}
+
+impl HourlyData {
+    /// Creates an hourly record, each value is a `(value, units)` tuple like `DailyData::new`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        timestamp: NaiveDateTime,
+        temperature: (f64, String),
+        rh: Option<(f64, String)>,
+        dewpoint: Option<(f64, String)>,
+        precip: Option<(f64, String)>,
+        rs: Option<(f64, String)>,
+        ea: Option<(f64, String)>,
+        wind_speed: Option<(f64, String)>,
+    ) -> Result<HourlyData, ClimateError> {
+        let date = timestamp.date();
+        let split = |value: Option<(f64, String)>, name: &str| match value {
+            Some((_, units)) if units.is_empty() => Err(ClimateError::missing_units(name, date)),
+            Some((value, units)) => Ok((Some(value), Some(units))),
+            None => Ok((None, None)),
+        };
+        if temperature.1.is_empty() {
+            return Err(ClimateError::missing_units("temperature", date));
+        }
+
+        let (rh, rh_units) = split(rh, "rh")?;
+        let (dewpoint, dewpoint_units) = split(dewpoint, "dewpoint")?;
+        let (precip, precip_units) = split(precip, "precip")?;
+        let (rs, rs_units) = split(rs, "rs")?;
+        let (ea, ea_units) = split(ea, "ea")?;
+        let (wind_speed, ws_units) = split(wind_speed, "wind_speed")?;
+        Ok(HourlyData {
+            timestamp,
+            temperature: temperature.0,
+            temperature_units: temperature.1,
+            rh,
+            rh_units,
+            dewpoint,
+            dewpoint_units,
+            precip,
+            precip_units,
+            rs,
+            rs_units,
+            ea,
+            ea_units,
+            wind_speed,
+            ws_units,
+            short_refet: None,
+            tall_refet: None,
+        })
+    }
+
+    fn to_output(&self) -> Result<HourlyOutput, ClimateError> {
+        let date = self.timestamp.date();
+        let convert = |value: Option<f64>,
+                       units: &Option<String>,
+                       name: &str,
+                       convert: fn(f64, &str) -> Result<f64, ClimateError>| {
+            match (value, units.as_deref()) {
+                (Some(value), Some(units)) => convert(value, units)
+                    .map(Some)
+                    .map_err(|e| e.with_context(name, date)),
+                (Some(_), None) => Err(ClimateError::missing_units(name, date)),
+                (None, _) => Ok(None),
+            }
+        };
+
+        let mut output = HourlyOutput::new();
+        output.set_datetime(self.timestamp);
+        output.set_temperature(
+            convert_temp_to_c(self.temperature, &self.temperature_units)
+                .map_err(|e| e.with_context("temperature", date))?,
+        );
+        output.set_rh(convert(self.rh, &self.rh_units, "rh", check_rh_units)?);
+        output.set_dewpoint(convert(
+            self.dewpoint,
+            &self.dewpoint_units,
+            "dewpoint",
+            convert_temp_to_c,
+        )?);
+        output.set_precip(convert(
+            self.precip,
+            &self.precip_units,
+            "precip",
+            convert_precip_to_mm,
+        )?);
+        output.set_rs(convert(
+            self.rs,
+            &self.rs_units,
+            "rs",
+            convert_hourly_rs_to_mj,
+        )?);
+        output.set_ea(convert(self.ea, &self.ea_units, "ea", convert_ea_to_kpa)?);
+        output.set_ws(convert(
+            self.wind_speed,
+            &self.ws_units,
+            "wind_speed",
+            convert_ws_to_ms,
+        )?);
+        Ok(output)
+    }
+}
+
+/// Converts the solar radiation for an hour to MJ/m², a total without a period is taken as the
+/// hour's total and W/m² as the mean flux over the hour.
+fn convert_hourly_rs_to_mj(value: f64, actual_units: &str) -> Result<f64, ClimateError> {
+    RadiantExposure::parse(value, actual_units)?
+        .over(Period::Hour)
+        .value_in(RadiantExposureUnits::MegaJoulesPerSquareMeterPerHour)
+}
+
+impl StationData {
+    /// Moves a timestamp read from the local clock to local standard time. In the repeated hour
+    /// when daylight saving time ends the first, daylight, reading is assumed; clock times
+    /// skipped when it starts are an error, as is a station without a time zone.
+    pub fn to_standard_time(&self, local: NaiveDateTime) -> Result<NaiveDateTime, ClimateError> {
+        let time_zone = self.time_zone.ok_or_else(|| {
+            ClimateError::invalid_value(format!("Station {} has no time zone", self.name))
+        })?;
+        let offset = match time_zone.offset_from_local_datetime(&local) {
+            LocalResult::Single(offset) | LocalResult::Ambiguous(offset, _) => offset,
+            LocalResult::None => {
+                return Err(ClimateError::invalid_value(format!(
+                    "{} does not exist on the {} clock",
+                    local, time_zone
+                )))
+            }
+        };
+        Ok(local - offset.dst_offset())
+    }
+
+    /// Longitude of the standard meridian of the station's time zone in degrees, positive east.
+    pub fn standard_meridian(&self) -> Option<f64> {
+        let time_zone = self.time_zone?;
+        let offset = time_zone.offset_from_utc_datetime(&Utc::now().naive_utc());
+        Some(offset.base_utc_offset().num_seconds() as f64 / 240.0)
+    }
+
+    pub fn add_hourly_records(&mut self, records: Vec<HourlyData>) {
+        self.hourly_data = records;
+    }
+
+    /// Converts the hourly records to `HourlyOutput` in SI units with the station's location, wind
+    /// speed is adjusted from the station's wind height to 2 m.
+    pub fn hourly_to_output(&self) -> Result<Vec<HourlyOutput>, ClimateError> {
+        let mut result = Vec::with_capacity(self.hourly_data.len());
+        for hourly_data in &self.hourly_data {
+            let mut output = hourly_data.to_output()?;
+            output.set_latitude(self.latitude);
+            output.set_longitude(self.longitude);
+            output.set_standard_meridian(self.standard_meridian());
+            output.set_z(self.elevation);
+            output.set_ws(
+                self.ws_at_2m(output.get_ws())
+                    .map_err(|e| e.with_context("wind_speed", hourly_data.timestamp.date()))?,
+            );
+            output.set_wz(Some(2.0));
+            output.set_station_id(self.id);
+            output.set_station_name(self.name.clone());
+            result.push(output);
+        }
+        Ok(result)
+    }
+
+    /// Computes the hourly ASCE standardized reference ET for every hourly record, storing ETos in
+    /// `short_refet` and ETrs in `tall_refet`. Hours missing rs, wind speed or humidity are left
+    /// empty. Returns the hourly results, which `refet::daily_sums` totals by day.
+    pub fn calculate_hourly_refet(&mut self) -> Result<Vec<HourlyRefEt>, ClimateError> {
+        let outputs = self.hourly_to_output()?;
+        let mut computed = Vec::new();
+        for (hourly_data, refet) in self.hourly_data.iter_mut().zip(hourly_refet(&outputs)) {
+            match refet {
+                Ok(refet) => {
+                    hourly_data.short_refet = Some(refet.etos);
+                    hourly_data.tall_refet = Some(refet.etrs);
+                    computed.push(refet);
+                }
+                Err(_) => {
+                    hourly_data.short_refet = None;
+                    hourly_data.tall_refet = None;
+                }
+            }
+        }
+
+        Ok(computed)
+    }
+
+    /// Aggregates the hourly records to daily records in SI units, one for each standard time day
+    /// with at least `min_hours` temperatures, in date order.
+    ///
+    /// The hour ending at midnight closes the day before. Days are standard time days, so each
+    /// has 24 hours even when daylight saving time starts or ends. Tmin and tmax are the lowest
+    /// and highest hourly temperatures, rhmin and rhmax the lowest and highest rh, dewpoint, ea
+    /// and wind speed are averaged and precipitation is summed. Optional values also need
+    /// `min_hours` readings, except radiation, which is summed only when all 24 hours are present
+    /// since a missing hour could be any part of the daylight curve.
+    pub fn hourly_to_daily(&self, min_hours: usize) -> Result<Vec<DailyData>, ClimateError> {
+        let mut days: BTreeMap<NaiveDate, Vec<HourlyOutput>> = BTreeMap::new();
+        for hourly_data in &self.hourly_data {
+            let date = (hourly_data.timestamp - Duration::hours(1)).date();
+            days.entry(date).or_default().push(hourly_data.to_output()?);
+        }
+
+        let mut result = Vec::with_capacity(days.len());
+        for (date, hours) in days {
+            let values = |value: fn(&HourlyOutput) -> Option<f64>| {
+                let values: Vec<f64> = hours.iter().filter_map(value).collect();
+                (values.len() >= min_hours.max(1)).then_some(values)
+            };
+            let min = |values: Vec<f64>| values.into_iter().fold(f64::INFINITY, f64::min);
+            let max = |values: Vec<f64>| values.into_iter().fold(f64::NEG_INFINITY, f64::max);
+            let mean = |values: Vec<f64>| values.iter().sum::<f64>() / values.len() as f64;
+            let with_units =
+                |value: Option<f64>, units: &str| value.map(|v| (v, units.to_string()));
+
+            let temperatures = match values(|h| Some(h.get_temperature())) {
+                Some(temperatures) => temperatures,
+                None => continue,
+            };
+            let rh = values(HourlyOutput::get_rh);
+            result.push(DailyData::new(
+                date,
+                (min(temperatures.clone()), "°C".to_string()),
+                (max(temperatures), "°C".to_string()),
+                with_units(rh.clone().map(min), "%"),
+                with_units(rh.map(max), "%"),
+                with_units(values(HourlyOutput::get_dewpoint).map(mean), "°C"),
+                with_units(
+                    values(HourlyOutput::get_precip).map(|v| v.iter().sum()),
+                    "mm",
+                ),
+                with_units(
+                    values(HourlyOutput::get_rs)
+                        .filter(|v| v.len() == 24)
+                        .map(|v| v.iter().sum()),
+                    "MJ/m²",
+                ),
+                with_units(values(HourlyOutput::get_ea).map(mean), "kPa"),
+                with_units(values(HourlyOutput::get_ws).map(mean), "m/s"),
+            )?);
+        }
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono_tz::America::Chicago;
+
+    fn station() -> StationData {
+        StationData::new(
+            "Mead".to_string(),
+            "test".to_string(),
+            41.15,
+            -96.49,
+            350.0,
+            3.0,
+            Some(1),
+        )
+        .with_time_zone(Chicago)
+    }
+
+    fn at(day: u32, hour: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2023, 3, day)
+            .unwrap()
+            .and_hms_opt(hour, 0, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn local_clock_to_standard_time() {
+        let station = station();
+        assert_eq!(station.standard_meridian(), Some(-90.0));
+        // daylight saving time starts at 02:00 on March 12th 2023
+        assert_eq!(station.to_standard_time(at(11, 14)).unwrap(), at(11, 14));
+        assert_eq!(station.to_standard_time(at(12, 14)).unwrap(), at(12, 13));
+        assert!(station.to_standard_time(at(12, 2)).is_err());
+    }
+
+    #[test]
+    fn hourly_records_to_daily() {
+        let mut station = station();
+        let mut records = Vec::new();
+        // the local clock skips 02:00 on March 12th, moved to standard time the day is complete
+        for hour in 1..=24 {
+            let local = at(12, 0) + Duration::hours(hour);
+            let local = if local >= at(12, 2) {
+                local + Duration::hours(1)
+            } else {
+                local
+            };
+            let timestamp = station.to_standard_time(local).unwrap();
+            let h = hour as f64;
+            records.push(
+                HourlyData::new(
+                    timestamp,
+                    (32.0 + h, "F".to_string()),
+                    Some((100.0 - h, "%".to_string())),
+                    None,
+                    Some((if hour == 8 { 0.1 } else { 0.0 }, "in".to_string())),
+                    Some((
+                        if (7..19).contains(&hour) { 500.0 } else { 0.0 },
+                        "W/m²".to_string(),
+                    )),
+                    None,
+                    Some((7.2, "km/h".to_string())),
+                )
+                .unwrap(),
+            );
+        }
+        station.add_hourly_records(records);
+
+        let outputs = station.hourly_to_output().unwrap();
+        assert_eq!(outputs[23].get_datetime(), at(13, 0));
+        assert_eq!(outputs[0].get_standard_meridian(), Some(-90.0));
+        assert!((outputs[0].get_temperature() - 5.0 / 9.0).abs() < 1e-9);
+        // 7.2 km/h at the station's 3 m is 2 m/s, adjusted to 2 m
+        let u2 = crate::equations::wind_speed_at_2m(2.0, 3.0);
+        assert!((outputs[0].get_ws().unwrap() - u2).abs() < 1e-9);
+
+        let daily = station.hourly_to_daily(22).unwrap();
+        assert_eq!(daily.len(), 1);
+        let day = &daily[0];
+        assert_eq!(day.date, NaiveDate::from_ymd_opt(2023, 3, 12).unwrap());
+        assert!((day.tmin - 5.0 / 9.0).abs() < 1e-9);
+        assert!((day.tmax - 24.0 * 5.0 / 9.0).abs() < 1e-9);
+        assert_eq!((day.rhmin, day.rhmax), (Some(76.0), Some(99.0)));
+        assert!((day.precip.unwrap() - 2.54).abs() < 1e-9);
+        assert!((day.rs.unwrap() - 12.0 * 500.0 * 0.0036).abs() < 1e-9);
+        assert!((day.wind_speed.unwrap() - 2.0).abs() < 1e-9);
+
+        assert!(station.hourly_to_daily(25).unwrap().is_empty());
+
+        // without the noon hour the day is kept but its radiation can't be totalled
+        station.hourly_data.remove(11);
+        let daily = station.hourly_to_daily(22).unwrap();
+        assert_eq!((daily.len(), daily[0].rs), (1, None));
+        assert!((daily[0].precip.unwrap() - 2.54).abs() < 1e-9);
+    }
+
+    #[test]
+    fn hourly_refet_sums_near_daily_method() {
+        use crate::equations::{hourly_extraterrestrial_radiation, solar_time_angle};
+        use crate::refet::daily_sums;
+        use std::f64::consts::PI;
+
+        let mut station = station();
+        let date = NaiveDate::from_ymd_opt(2023, 7, 1).unwrap();
+        let latitude = station.latitude.to_radians();
+        let records = (1..=24)
+            .map(|hour| {
+                let h = hour as f64;
+                let omega = solar_time_angle(h - 0.5, station.longitude, -90.0, 182);
+                // a clear day with the warmest hour at 15:00
+                let rs = 0.75 * hourly_extraterrestrial_radiation(latitude, 182, omega);
+                HourlyData::new(
+                    date.and_hms_opt(0, 0, 0).unwrap() + Duration::hours(hour),
+                    (24.0 + 8.0 * (PI * (h - 9.0) / 12.0).sin(), "°C".to_string()),
+                    None,
+                    Some((15.0, "°C".to_string())),
+                    None,
+                    Some((rs, "MJ/m²".to_string())),
+                    None,
+                    Some((3.0, "m/s".to_string())),
+                )
+                .unwrap()
+            })
+            .collect();
+        station.add_hourly_records(records);
+
+        let hourly = station.calculate_hourly_refet().unwrap();
+        assert_eq!(hourly.len(), 24);
+        assert!(station.hourly_data.iter().all(|h| h.short_refet.is_some()));
+        let sums = daily_sums(&hourly);
+        assert_eq!((sums.len(), sums[0].hours), (1, 24));
+
+        let daily = station.hourly_to_daily(24).unwrap();
+        station.add_daily_records(daily);
+        station.calculate_refet().unwrap();
+        let daily_etos = station.daily_data[0].short_refet.unwrap();
+        let daily_etrs = station.daily_data[0].tall_refet.unwrap();
+        assert!((sums[0].etos / daily_etos - 1.0).abs() < 0.1);
+        assert!((sums[0].etrs / daily_etrs - 1.0).abs() < 0.1);
+    }
+}
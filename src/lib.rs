@@ -1,4 +1,15 @@
 pub mod data_model;
-pub mod units;
+pub mod database;
+pub mod equations;
+pub mod error;
+pub mod estimation;
+pub mod gap_fill;
+pub mod hourly;
 mod date_operations;
 pub mod output;
+pub mod qc;
+pub mod quantity;
+pub mod readers;
+pub mod refet;
+pub mod units;
+pub mod writers;
@@ -1,5 +1,8 @@
-use chrono::{NaiveDate, Utc};
+use crate::data_model::Variable;
+use crate::estimation::{Estimate, EstimationMethod};
+use chrono::{NaiveDate, NaiveDateTime, Utc};
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Output {
     tmax: f64,  // temperature maximum in Celsius
     tmin: f64,  // temperature minimum in Celsius
@@ -9,10 +12,15 @@ pub struct Output {
     ea: Option<f64>,  // vapor pressure deficit in kPa
     rs: Option<f64>,  // solar radiation in MJ/m²
     ws: Option<f64>,  // wind speed in m/s
+    precip: Option<f64>,  // precipitation in mm
     wz: Option<f64>,  // height of wind speed m
+    pressure: Option<f64>,  // measured station pressure in kPa
     z: f64,  // elevation in m
     latitude: f64,  // latitude in radians
     date: NaiveDate, // date and time of the reading
+    station_id: Option<i32>,  // id of the station the reading came from
+    station_name: String,  // name of the station the reading came from
+    estimates: Vec<Estimate>,  // values that were estimated rather than measured
 }
 
 impl Default for Output {
@@ -22,6 +30,7 @@ impl Default for Output {
 }
 
 impl Output {
+    #[allow(clippy::too_many_arguments)]
     pub fn new_with_values(tmax: f64, tmin: f64, rhmax: Option<f64>, rhmin: Option<f64>, dewpoint: Option<f64>, ea: Option<f64>,
                            rs: Option<f64>, ws: Option<f64>, wz: Option<f64>, z: f64, latitude: f64, date: NaiveDate) -> Output {
         Output {
@@ -33,10 +42,15 @@ impl Output {
             ea,
             rs,
             ws,
+            precip: None,
             wz,
+            pressure: None,
             z,
             latitude,
             date,
+            station_id: None,
+            station_name: String::new(),
+            estimates: Vec::new(),
         }
     }
 
@@ -50,10 +64,15 @@ impl Output {
             ea: None,
             rs: None,
             ws: None,
+            precip: None,
             wz: None,
+            pressure: None,
             z: 0.0,
             latitude: 0.0,
             date: Utc::now().date_naive(),
+            station_id: None,
+            station_name: String::new(),
+            estimates: Vec::new(),
         }
     }
 
@@ -130,19 +149,34 @@ impl Output {
         self.ws = ws;
     }
 
+    // precip getters and setters
+    pub fn get_precip(&self) -> Option<f64> {
+        self.precip
+    }
+
+    pub fn set_precip(&mut self, precip: Option<f64>) {
+        self.precip = precip;
+    }
+
     // wz getters and setters
     pub fn get_wz(&self) -> f64 {
-        if self.wz.is_some() {
-            self.wz.unwrap()
-        } else {
-            2.0  // default to 2.0 meters is the standard height for these stations
-        }
+        // default to 2.0 meters is the standard height for these stations
+        self.wz.unwrap_or(2.0)
     }
 
     pub fn set_wz(&mut self, wz: Option<f64>) {
         self.wz = wz;
     }
 
+    // pressure getters and setters
+    pub fn get_pressure(&self) -> Option<f64> {
+        self.pressure
+    }
+
+    pub fn set_pressure(&mut self, pressure: Option<f64>) {
+        self.pressure = pressure;
+    }
+
     // z getters and setters
     pub fn get_z(&self) -> f64 {
         self.z
@@ -170,4 +204,220 @@ impl Output {
     pub fn set_date(&mut self, date: NaiveDate) {
         self.date = date;
     }
-}
\ No newline at end of file
+
+    // station id getters and setters
+    pub fn get_station_id(&self) -> Option<i32> {
+        self.station_id
+    }
+
+    pub fn set_station_id(&mut self, station_id: Option<i32>) {
+        self.station_id = station_id;
+    }
+
+    // station name getters and setters
+    pub fn get_station_name(&self) -> &str {
+        &self.station_name
+    }
+
+    pub fn set_station_name(&mut self, station_name: String) {
+        self.station_name = station_name;
+    }
+
+    // estimates getters and setters
+    pub fn get_estimates(&self) -> &[Estimate] {
+        &self.estimates
+    }
+
+    // add_estimate marks a value as estimated, replacing any earlier estimate for the variable
+    pub fn add_estimate(&mut self, variable: Variable, method: EstimationMethod) {
+        self.estimates.retain(|estimate| estimate.variable != variable);
+        self.estimates.push(Estimate { variable, method });
+    }
+
+    pub fn is_estimated(&self, variable: Variable) -> bool {
+        self.estimates.iter().any(|estimate| estimate.variable == variable)
+    }
+}
+
+/// An hourly record in SI units, keyed by the end of the hour in the station's standard time.
+pub struct HourlyOutput {
+    temperature: f64,  // mean air temperature in Celsius
+    rh: Option<f64>,  // relative humidity in %
+    dewpoint: Option<f64>,  // dewpoint temperature in Celsius
+    ea: Option<f64>,  // actual vapor pressure in kPa
+    rs: Option<f64>,  // solar radiation in MJ/m² for the hour
+    ws: Option<f64>,  // wind speed in m/s
+    precip: Option<f64>,  // precipitation in mm
+    wz: Option<f64>,  // height of wind speed m
+    z: f64,  // elevation in m
+    latitude: f64,  // latitude in radians
+    longitude: f64,  // longitude in degrees, positive east
+    standard_meridian: Option<f64>,  // longitude of the time zone's standard meridian in degrees, positive east
+    datetime: NaiveDateTime,  // end of the hour in standard time
+    station_id: Option<i32>,  // id of the station the reading came from
+    station_name: String,  // name of the station the reading came from
+}
+
+impl Default for HourlyOutput {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HourlyOutput {
+    pub fn new() -> HourlyOutput {
+        HourlyOutput {
+            temperature: 0.0,
+            rh: None,
+            dewpoint: None,
+            ea: None,
+            rs: None,
+            ws: None,
+            precip: None,
+            wz: None,
+            z: 0.0,
+            latitude: 0.0,
+            longitude: 0.0,
+            standard_meridian: None,
+            datetime: Utc::now().naive_utc(),
+            station_id: None,
+            station_name: String::new(),
+        }
+    }
+
+    // temperature getters and setters
+    pub fn get_temperature(&self) -> f64 {
+        self.temperature
+    }
+
+    pub fn set_temperature(&mut self, temperature: f64) {
+        self.temperature = temperature;
+    }
+
+    // rh getters and setters
+    pub fn get_rh(&self) -> Option<f64> {
+        self.rh
+    }
+
+    pub fn set_rh(&mut self, rh: Option<f64>) {
+        self.rh = rh;
+    }
+
+    // dewpoint getters and setters
+    pub fn get_dewpoint(&self) -> Option<f64> {
+        self.dewpoint
+    }
+
+    pub fn set_dewpoint(&mut self, dewpoint: Option<f64>) {
+        self.dewpoint = dewpoint;
+    }
+
+    // ea getters and setters
+    pub fn get_ea(&self) -> Option<f64> {
+        self.ea
+    }
+
+    pub fn set_ea(&mut self, ea: Option<f64>) {
+        self.ea = ea;
+    }
+
+    // rs getters and setters
+    pub fn get_rs(&self) -> Option<f64> {
+        self.rs
+    }
+
+    pub fn set_rs(&mut self, rs: Option<f64>) {
+        self.rs = rs;
+    }
+
+    // ws getters and setters
+    pub fn get_ws(&self) -> Option<f64> {
+        self.ws
+    }
+
+    pub fn set_ws(&mut self, ws: Option<f64>) {
+        self.ws = ws;
+    }
+
+    // precip getters and setters
+    pub fn get_precip(&self) -> Option<f64> {
+        self.precip
+    }
+
+    pub fn set_precip(&mut self, precip: Option<f64>) {
+        self.precip = precip;
+    }
+
+    // wz getters and setters, the wind height defaults to 2 m
+    pub fn get_wz(&self) -> f64 {
+        self.wz.unwrap_or(2.0)
+    }
+
+    pub fn set_wz(&mut self, wz: Option<f64>) {
+        self.wz = wz;
+    }
+
+    // z getters and setters
+    pub fn get_z(&self) -> f64 {
+        self.z
+    }
+
+    pub fn set_z(&mut self, z: f64) {
+        self.z = z;
+    }
+
+    // latitude getters and setters
+    pub fn get_latitude(&self) -> f64 {
+        self.latitude
+    }
+
+    // set_latitude changes the latitude of the station from degrees to radians
+    pub fn set_latitude(&mut self, latitude: f64) {
+        self.latitude = latitude.to_radians();
+    }
+
+    // longitude getters and setters
+    pub fn get_longitude(&self) -> f64 {
+        self.longitude
+    }
+
+    pub fn set_longitude(&mut self, longitude: f64) {
+        self.longitude = longitude;
+    }
+
+    // standard meridian getters and setters
+    pub fn get_standard_meridian(&self) -> Option<f64> {
+        self.standard_meridian
+    }
+
+    pub fn set_standard_meridian(&mut self, standard_meridian: Option<f64>) {
+        self.standard_meridian = standard_meridian;
+    }
+
+    // datetime getters and setters
+    pub fn get_datetime(&self) -> NaiveDateTime {
+        self.datetime
+    }
+
+    pub fn set_datetime(&mut self, datetime: NaiveDateTime) {
+        self.datetime = datetime;
+    }
+
+    // station id getters and setters
+    pub fn get_station_id(&self) -> Option<i32> {
+        self.station_id
+    }
+
+    pub fn set_station_id(&mut self, station_id: Option<i32>) {
+        self.station_id = station_id;
+    }
+
+    // station name getters and setters
+    pub fn get_station_name(&self) -> &str {
+        &self.station_name
+    }
+
+    pub fn set_station_name(&mut self, station_name: String) {
+        self.station_name = station_name;
+    }
+}
@@ -0,0 +1,283 @@
+use crate::data_model::{convert_temp_to_c, StationData, Variable};
+use crate::equations::{
+    dewpoint_from_vapor_pressure, saturation_vapor_pressure, vapor_pressure_from_rh,
+};
+use crate::error::ClimateError;
+use crate::qc::{flag, FlagKind};
+use crate::quantity::{TemperatureDifference, TemperatureDifferenceUnits};
+use chrono::NaiveDate;
+
+/// Settings for the humidity and vapor pressure checks.
+#[derive(Debug, Clone)]
+pub struct HumidityQc {
+    /// how far dewpoint may be above tmax before it is flagged, allows for sensor precision
+    pub dewpoint_tolerance: TemperatureDifference,
+    /// Tmin - Tdew above this suggests an arid or non-reference site
+    pub aridity_threshold: TemperatureDifference,
+    /// when set, the report includes an ea series adjusted toward reference conditions
+    pub correct_ea: bool,
+    /// Tmin - Tdew expected at a well watered reference site, used for the corrected ea
+    pub reference_offset: TemperatureDifference,
+}
+
+impl Default for HumidityQc {
+    fn default() -> Self {
+        HumidityQc {
+            dewpoint_tolerance: TemperatureDifference::new(
+                0.5,
+                TemperatureDifferenceUnits::Celsius,
+            ),
+            aridity_threshold: TemperatureDifference::new(3.0, TemperatureDifferenceUnits::Celsius),
+            correct_ea: false,
+            reference_offset: TemperatureDifference::new(2.0, TemperatureDifferenceUnits::Celsius),
+        }
+    }
+}
+
+/// Vapor pressure for one day in kPa, before and after the humidity adjustment.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CorrectedVaporPressure {
+    pub date: NaiveDate,
+    pub measured: f64,
+    pub corrected: f64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct HumidityQcReport {
+    pub flags_added: usize,
+    /// present when `correct_ea` is set, one entry per day with usable humidity data
+    pub corrected_ea: Option<Vec<CorrectedVaporPressure>>,
+}
+
+/// Checks rhmin, rhmax, dewpoint and ea on every `DailyData` record. RH outside 0-100, rhmin above
+/// rhmax, dewpoint above tmax and ea above saturation at tmax are flagged, then Tdew (measured, or
+/// derived from ea or RH) is compared with Tmin for the aridity check.
+///
+/// With `correct_ea` set, days that fail the aridity check get an ea computed from
+/// Tdew = Tmin - `reference_offset` to approximate irrigated reference conditions, all other
+/// days carry their measured ea. The `DailyData` values themselves are not changed.
+pub fn check_humidity(
+    station: &mut StationData,
+    config: &HumidityQc,
+) -> Result<HumidityQcReport, ClimateError> {
+    let mut report = HumidityQcReport {
+        flags_added: 0,
+        corrected_ea: if config.correct_ea {
+            Some(Vec::new())
+        } else {
+            None
+        },
+    };
+
+    for daily_data in station.daily_data.iter_mut() {
+        let tmin = convert_temp_to_c(daily_data.tmin, &daily_data.tmin_units)
+            .map_err(|e| e.with_context(Variable::Tmin.name(), daily_data.date))?;
+        let tmax = convert_temp_to_c(daily_data.tmax, &daily_data.tmax_units)
+            .map_err(|e| e.with_context(Variable::Tmax.name(), daily_data.date))?;
+        let rhmin = daily_data.value_in_output_units(Variable::RhMin)?;
+        let rhmax = daily_data.value_in_output_units(Variable::RhMax)?;
+        let dewpoint = daily_data.value_in_output_units(Variable::Dewpoint)?;
+        let ea = daily_data.value_in_output_units(Variable::Ea)?;
+
+        let mut rh_valid = true;
+        for (variable, value) in [(Variable::RhMin, rhmin), (Variable::RhMax, rhmax)] {
+            if let Some(value) = value {
+                if !(0.0..=100.0).contains(&value) {
+                    rh_valid = false;
+                    let message =
+                        format!("{} {:.1}% is outside of 0 to 100%", variable.name(), value);
+                    report.flags_added += flag(daily_data, variable, FlagKind::OutOfRange, message);
+                }
+            }
+        }
+
+        if let (Some(rhmin), Some(rhmax)) = (rhmin, rhmax) {
+            if rhmin > rhmax {
+                rh_valid = false;
+                let message = format!("rhmin {:.1}% is above rhmax {:.1}%", rhmin, rhmax);
+                report.flags_added += flag(
+                    daily_data,
+                    Variable::RhMin,
+                    FlagKind::Inversion,
+                    message.clone(),
+                );
+                report.flags_added +=
+                    flag(daily_data, Variable::RhMax, FlagKind::Inversion, message);
+            }
+        }
+
+        let mut dewpoint_valid = true;
+        if let Some(dewpoint) = dewpoint {
+            if dewpoint > tmax + config.dewpoint_tolerance.celsius() {
+                dewpoint_valid = false;
+                let message = format!("dewpoint {:.1} °C is above tmax {:.1} °C", dewpoint, tmax);
+                report.flags_added += flag(
+                    daily_data,
+                    Variable::Dewpoint,
+                    FlagKind::OutOfRange,
+                    message,
+                );
+            }
+        }
+
+        let mut ea_valid = true;
+        if let Some(ea) = ea {
+            let es_tmax = saturation_vapor_pressure(tmax + config.dewpoint_tolerance.celsius());
+            if ea < 0.0 || ea > es_tmax {
+                ea_valid = false;
+                let message = format!(
+                    "ea {:.3} kPa is outside of 0 to {:.3} kPa, saturation at tmax",
+                    ea, es_tmax
+                );
+                report.flags_added += flag(daily_data, Variable::Ea, FlagKind::OutOfRange, message);
+            }
+        }
+
+        // the humidity source in the order ASCE-EWRI recommends: ea, dewpoint, then RH extremes
+        let measured = if let (Some(ea), true) = (ea, ea_valid) {
+            Some((ea, Variable::Ea))
+        } else if let (Some(dewpoint), true) = (dewpoint, dewpoint_valid) {
+            Some((saturation_vapor_pressure(dewpoint), Variable::Dewpoint))
+        } else if let (Some(rhmin), Some(rhmax), true) = (rhmin, rhmax, rh_valid) {
+            Some((
+                vapor_pressure_from_rh(tmin, tmax, rhmin, rhmax),
+                Variable::RhMax,
+            ))
+        } else {
+            None
+        };
+
+        let (measured, source) = match measured {
+            Some(measured) if measured.0 > 0.0 => measured,
+            _ => continue,
+        };
+
+        let tdew = match (dewpoint, source) {
+            (Some(dewpoint), Variable::Dewpoint) => dewpoint,
+            _ => dewpoint_from_vapor_pressure(measured),
+        };
+
+        let arid = tmin - tdew > config.aridity_threshold.celsius();
+        if arid {
+            let message = format!(
+                "tmin {:.1} °C is {:.1} °C above dewpoint {:.1} °C",
+                tmin,
+                tmin - tdew,
+                tdew
+            );
+            report.flags_added += flag(daily_data, source, FlagKind::Aridity, message);
+        }
+
+        if let Some(corrected_ea) = report.corrected_ea.as_mut() {
+            let corrected = if arid {
+                saturation_vapor_pressure(tmin - config.reference_offset.celsius())
+            } else {
+                measured
+            };
+            corrected_ea.push(CorrectedVaporPressure {
+                date: daily_data.date,
+                measured,
+                corrected,
+            });
+        }
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_model::DailyData;
+
+    fn station_with(days: Vec<DailyData>) -> StationData {
+        let mut station = StationData::new(
+            "Test".to_string(),
+            "test".to_string(),
+            41.0,
+            -96.0,
+            350.0,
+            2.0,
+            None,
+        );
+        station.add_daily_records(days);
+        station
+    }
+
+    fn day(
+        day: u32,
+        tmin: f64,
+        rh: Option<(f64, f64)>,
+        dewpoint: Option<f64>,
+        ea: Option<f64>,
+    ) -> DailyData {
+        DailyData::new(
+            NaiveDate::from_ymd_opt(2023, 7, day).unwrap(),
+            (tmin, "°C".to_string()),
+            (32.0, "°C".to_string()),
+            rh.map(|(rhmin, _)| (rhmin, "%".to_string())),
+            rh.map(|(_, rhmax)| (rhmax, "%".to_string())),
+            dewpoint.map(|d| (d, "°C".to_string())),
+            None,
+            None,
+            ea.map(|ea| (ea, "Pa".to_string())),
+            None,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn flags_invalid_humidity() {
+        let mut station = station_with(vec![
+            day(1, 18.0, Some((30.0, 105.0)), None, None),
+            day(2, 18.0, Some((70.0, 60.0)), None, None),
+            day(3, 18.0, None, Some(33.5), None),
+            day(4, 18.0, Some((35.0, 85.0)), Some(16.5), Some(1900.0)),
+        ]);
+
+        let report = check_humidity(&mut station, &HumidityQc::default()).unwrap();
+        assert_eq!(report.flags_added, 4);
+        assert!(report.corrected_ea.is_none());
+
+        assert_eq!(
+            station.daily_data[0].flags_for(Variable::RhMax)[0].kind,
+            FlagKind::OutOfRange
+        );
+        assert!(!station.daily_data[0].is_flagged(Variable::RhMin));
+        assert_eq!(
+            station.daily_data[1].flags_for(Variable::RhMin)[0].kind,
+            FlagKind::Inversion
+        );
+        assert_eq!(
+            station.daily_data[2].flags_for(Variable::Dewpoint)[0].kind,
+            FlagKind::OutOfRange
+        );
+        assert!(station.daily_data[3].qc_flags.is_empty());
+    }
+
+    #[test]
+    fn aridity_check_and_corrected_ea() {
+        let mut station = station_with(vec![
+            day(1, 18.0, None, Some(6.0), None),
+            day(2, 18.0, None, Some(16.5), None),
+        ]);
+
+        let config = HumidityQc {
+            correct_ea: true,
+            ..HumidityQc::default()
+        };
+        let report = check_humidity(&mut station, &config).unwrap();
+
+        assert_eq!(
+            station.daily_data[0].flags_for(Variable::Dewpoint)[0].kind,
+            FlagKind::Aridity
+        );
+        assert!(!station.daily_data[1].is_flagged(Variable::Dewpoint));
+
+        let corrected = report.corrected_ea.unwrap();
+        assert_eq!(corrected.len(), 2);
+        assert_eq!(corrected[0].measured, saturation_vapor_pressure(6.0));
+        assert_eq!(corrected[0].corrected, saturation_vapor_pressure(16.0));
+        assert_eq!(corrected[1].corrected, corrected[1].measured);
+    }
+}
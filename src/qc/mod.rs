@@ -0,0 +1,107 @@
+//! Quality control checks that run over `StationData::daily_data`.
+//!
+//! Checks never remove or change a `DailyData` record, they attach a `QcFlag` to the individual
+//! value that failed so downstream refet runs can decide what to trust.
+
+pub mod humidity;
+pub mod precipitation;
+pub mod solar;
+pub mod temperature;
+
+use crate::data_model::{DailyData, StationData, Variable};
+
+/// The reason a value was flagged.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FlagKind {
+    /// tmax is below tmin for the same day
+    Inversion,
+    /// the value is outside of the physically plausible range for the variable
+    OutOfRange,
+    /// the value jumps away from both the day before and the day after
+    Spike,
+    /// the sensor reported the same value for too many consecutive days
+    FlatLine,
+    /// solar radiation is above the clear-sky envelope for the day
+    ExceedsClearSky,
+    /// the value was corrected in place, the message records how
+    Adjusted,
+    /// dewpoint well below tmin, the site is arid or not at reference conditions
+    Aridity,
+    /// a large event that no neighboring station recorded
+    Isolated,
+    /// a multi-day total reported on a single day
+    Accumulation,
+    /// the gauge caught much less than its neighbors
+    Undercatch,
+    /// a trace of precipitation, too small to measure and recorded as zero
+    Trace,
+    /// the data provider's own quality control failed the value, the message has their flag
+    SourceRejected,
+}
+
+impl FlagKind {
+    pub fn name(&self) -> &str {
+        match self {
+            FlagKind::Inversion => "Inversion",
+            FlagKind::OutOfRange => "Out of Range",
+            FlagKind::Spike => "Spike",
+            FlagKind::FlatLine => "Flat Line",
+            FlagKind::ExceedsClearSky => "Exceeds Clear Sky",
+            FlagKind::Adjusted => "Adjusted",
+            FlagKind::Aridity => "Aridity",
+            FlagKind::Isolated => "Isolated",
+            FlagKind::Accumulation => "Accumulation",
+            FlagKind::Undercatch => "Undercatch",
+            FlagKind::Trace => "Trace",
+            FlagKind::SourceRejected => "Source Rejected",
+        }
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct QcFlag {
+    pub variable: Variable,
+    pub kind: FlagKind,
+    pub message: String,
+}
+
+impl QcFlag {
+    pub fn new(variable: Variable, kind: FlagKind, message: String) -> QcFlag {
+        QcFlag {
+            variable,
+            kind,
+            message,
+        }
+    }
+}
+
+/// Returns the indexes of the daily records ordered by date so checks that compare neighboring
+/// days don't depend on the order records were added to the station.
+pub(crate) fn date_order(daily_data: &[DailyData]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..daily_data.len()).collect();
+    order.sort_by_key(|i| daily_data[*i].date);
+    order
+}
+
+/// Adds a flag to a record and returns 1 if it was new, so checks can count the flags they add.
+pub(crate) fn flag(
+    daily_data: &mut DailyData,
+    variable: Variable,
+    kind: FlagKind,
+    message: String,
+) -> usize {
+    let before = daily_data.qc_flags.len();
+    daily_data.add_flag(QcFlag::new(variable, kind, message));
+    daily_data.qc_flags.len() - before
+}
+
+/// Returns the number of values in the station that carry at least one flag.
+pub fn flagged_count(station: &StationData, variable: Variable) -> usize {
+    station
+        .daily_data
+        .iter()
+        .filter(|d| d.is_flagged(variable))
+        .count()
+}
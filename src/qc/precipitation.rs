@@ -0,0 +1,287 @@
+use crate::data_model::{StationData, Variable};
+use crate::error::ClimateError;
+use crate::qc::{flag, FlagKind};
+use chrono::{Duration, NaiveDate};
+use std::collections::HashMap;
+
+/// Settings for the precipitation checks, amounts are in mm.
+#[derive(Debug, Clone)]
+pub struct PrecipitationQc {
+    /// largest plausible daily total
+    pub max_daily_mm: f64,
+    /// stations within this distance are used as neighbors
+    pub neighbor_radius_km: f64,
+    /// events at or above this are checked against the neighbors for isolation and accumulation
+    pub large_event_mm: f64,
+    /// a neighbor that recorded at least this much counts as having seen the event
+    pub neighbor_wet_mm: f64,
+    /// number of dry or missing days before a large event that could hide an accumulation
+    pub accumulation_days: i64,
+    /// a day is checked for undercatch when the neighbor median is at least this much
+    pub undercatch_min_mm: f64,
+    /// the station is flagged when it caught less than this fraction of the neighbor median
+    pub undercatch_ratio: f64,
+}
+
+impl Default for PrecipitationQc {
+    fn default() -> Self {
+        PrecipitationQc {
+            max_daily_mm: 300.0,
+            neighbor_radius_km: 50.0,
+            large_event_mm: 25.0,
+            neighbor_wet_mm: 1.0,
+            accumulation_days: 3,
+            undercatch_min_mm: 10.0,
+            undercatch_ratio: 0.5,
+        }
+    }
+}
+
+/// Checks precip on every `DailyData` record of every station. Negative and implausibly large
+/// values are flagged on their own; the remaining checks compare each station with the stations
+/// within `neighbor_radius_km` of it:
+///
+/// * Isolated - a large event where no neighbor recorded rain the day before, of or after.
+/// * Accumulation - a large event after several dry or missing days that matches the neighbors'
+///   multi-day total better than their total for the day.
+/// * Undercatch - the station caught well under the neighbor median on a wet day.
+///
+/// Returns the number of new flags that were added.
+pub fn check_precipitation(
+    stations: &mut [StationData],
+    config: &PrecipitationQc,
+) -> Result<usize, ClimateError> {
+    let mut series: Vec<HashMap<NaiveDate, f64>> = Vec::with_capacity(stations.len());
+    for station in stations.iter() {
+        let mut values = HashMap::new();
+        for daily_data in &station.daily_data {
+            if let Some(precip) = daily_data.value_in_output_units(Variable::Precip)? {
+                values.insert(daily_data.date, precip);
+            }
+        }
+        series.push(values);
+    }
+
+    let neighbors: Vec<Vec<usize>> = (0..stations.len())
+        .map(|i| {
+            (0..stations.len())
+                .filter(|j| {
+                    *j != i && stations[i].distance_to(&stations[*j]) <= config.neighbor_radius_km
+                })
+                .collect()
+        })
+        .collect();
+
+    // the neighbor values on a date, skipping neighbors without a valid observation
+    let neighbor_values = |i: usize, date: NaiveDate| -> Vec<f64> {
+        neighbors[i]
+            .iter()
+            .filter_map(|j| series[*j].get(&date).copied())
+            .filter(|value| *value >= 0.0)
+            .collect()
+    };
+
+    let mut added = 0;
+    for (i, station) in stations.iter_mut().enumerate() {
+        for daily_data in station.daily_data.iter_mut() {
+            let date = daily_data.date;
+            let precip = match series[i].get(&date) {
+                Some(precip) => *precip,
+                None => continue,
+            };
+
+            if precip < 0.0 || precip > config.max_daily_mm {
+                let message = format!(
+                    "precip {:.1} mm is outside of 0 to {:.1} mm",
+                    precip, config.max_daily_mm
+                );
+                added += flag(daily_data, Variable::Precip, FlagKind::OutOfRange, message);
+                continue;
+            }
+
+            let today = neighbor_values(i, date);
+            if today.is_empty() {
+                continue;
+            }
+            let today_median = median(&today);
+
+            if precip >= config.large_event_mm {
+                let window: Vec<f64> = [date - Duration::days(1), date, date + Duration::days(1)]
+                    .iter()
+                    .flat_map(|d| neighbor_values(i, *d))
+                    .collect();
+                if window.iter().all(|value| *value < config.neighbor_wet_mm) {
+                    let message = format!(
+                        "precip {:.1} mm was not recorded by any of {} neighbors",
+                        precip,
+                        neighbors[i].len()
+                    );
+                    added += flag(daily_data, Variable::Precip, FlagKind::Isolated, message);
+                    continue;
+                }
+
+                // `map_or` rather than `is_none_or` so the crate doesn't need Rust 1.82
+                #[allow(clippy::unnecessary_map_or)]
+                let dry_before = (1..=config.accumulation_days).all(|days| {
+                    series[i]
+                        .get(&(date - Duration::days(days)))
+                        .map_or(true, |value| *value == 0.0)
+                });
+                if dry_before && config.accumulation_days > 0 {
+                    let previous_total: f64 = (1..=config.accumulation_days)
+                        .map(|days| {
+                            let values = neighbor_values(i, date - Duration::days(days));
+                            if values.is_empty() {
+                                0.0
+                            } else {
+                                median(&values)
+                            }
+                        })
+                        .sum();
+                    let multi_day = previous_total + today_median;
+                    if previous_total >= config.neighbor_wet_mm
+                        && (precip - multi_day).abs() < (precip - today_median).abs()
+                    {
+                        let message = format!(
+                            "precip {:.1} mm matches the neighbors' {}-day total of {:.1} mm",
+                            precip,
+                            config.accumulation_days + 1,
+                            multi_day
+                        );
+                        added += flag(
+                            daily_data,
+                            Variable::Precip,
+                            FlagKind::Accumulation,
+                            message,
+                        );
+                        continue;
+                    }
+                }
+            }
+
+            if today_median >= config.undercatch_min_mm
+                && precip < today_median * config.undercatch_ratio
+            {
+                let message = format!(
+                    "precip {:.1} mm is below {:.0}% of the neighbor median {:.1} mm",
+                    precip,
+                    config.undercatch_ratio * 100.0,
+                    today_median
+                );
+                added += flag(daily_data, Variable::Precip, FlagKind::Undercatch, message);
+            }
+        }
+    }
+
+    Ok(added)
+}
+
+// `% 2` rather than `is_multiple_of` so the crate doesn't need Rust 1.87
+#[allow(clippy::manual_is_multiple_of)]
+fn median(values: &[f64]) -> f64 {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let middle = sorted.len() / 2;
+    if sorted.len() % 2 == 0 {
+        (sorted[middle - 1] + sorted[middle]) / 2.0
+    } else {
+        sorted[middle]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_model::DailyData;
+
+    fn station(name: &str, latitude: f64, precip: &[f64]) -> StationData {
+        let mut station = StationData::new(
+            name.to_string(),
+            "test".to_string(),
+            latitude,
+            -98.0,
+            500.0,
+            2.0,
+            None,
+        );
+        let start = NaiveDate::from_ymd_opt(2023, 5, 1).unwrap();
+        let records = precip
+            .iter()
+            .enumerate()
+            .map(|(day, value)| {
+                DailyData::new(
+                    start + Duration::days(day as i64),
+                    (10.0, "°C".to_string()),
+                    (25.0, "°C".to_string()),
+                    None,
+                    None,
+                    None,
+                    Some((*value, "in".to_string())),
+                    None,
+                    None,
+                    None,
+                )
+                .unwrap()
+            })
+            .collect();
+        station.add_daily_records(records);
+        station
+    }
+
+    fn kinds(station: &StationData) -> Vec<Option<FlagKind>> {
+        station
+            .daily_data
+            .iter()
+            .map(|d| d.flags_for(Variable::Precip).first().map(|f| f.kind))
+            .collect()
+    }
+
+    #[test]
+    fn flags_against_neighbors() {
+        let mut stations = vec![
+            station(
+                "Target",
+                41.0,
+                &[0.0, 3.0, 0.0, 0.0, 0.0, 2.1, 0.1, -0.5, 20.0],
+            ),
+            station(
+                "North",
+                41.1,
+                &[0.0, 0.0, 0.0, 0.3, 0.3, 0.6, 1.0, 0.0, 0.0],
+            ),
+            station(
+                "South",
+                40.9,
+                &[0.0, 0.0, 0.0, 0.3, 0.3, 0.5, 0.9, 0.0, 0.0],
+            ),
+            station(
+                "Middle",
+                41.05,
+                &[0.0, 0.0, 0.0, 0.3, 0.3, 0.5, 0.9, 0.0, 0.0],
+            ),
+            // too far away to be a neighbor
+            station("Far", 43.0, &[0.0, 3.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0]),
+        ];
+
+        let added = check_precipitation(&mut stations, &PrecipitationQc::default()).unwrap();
+        assert_eq!(added, 5);
+
+        assert_eq!(
+            kinds(&stations[0]),
+            vec![
+                None,
+                Some(FlagKind::Isolated),
+                None,
+                None,
+                None,
+                Some(FlagKind::Accumulation),
+                Some(FlagKind::Undercatch),
+                Some(FlagKind::OutOfRange),
+                Some(FlagKind::OutOfRange),
+            ]
+        );
+        assert!(stations[1].daily_data.iter().all(|d| d.qc_flags.is_empty()));
+        assert!(stations[2].daily_data.iter().all(|d| d.qc_flags.is_empty()));
+        assert!(stations[4].daily_data.iter().all(|d| d.qc_flags.is_empty()));
+    }
+}
@@ -0,0 +1,232 @@
+use crate::data_model::{StationData, Variable};
+use crate::equations::{clear_sky_radiation, extraterrestrial_radiation};
+use crate::error::ClimateError;
+use crate::qc::{date_order, flag, FlagKind};
+use chrono::{Datelike, NaiveDate};
+
+/// Settings for the solar radiation checks.
+#[derive(Debug, Clone)]
+pub struct SolarQc {
+    /// rs is flagged when it is above `rso_tolerance` times the clear-sky radiation
+    pub rso_tolerance: f64,
+    /// rescale rs so the upper envelope of Rs/Rso matches 1.0 in each period (ASCE-EWRI Appendix D)
+    pub correct_drift: bool,
+    /// number of days in each period used to fit the upper envelope
+    pub period_days: i64,
+    /// number of highest Rs/Rso ratios averaged to form the envelope in each period
+    pub envelope_points: usize,
+    /// periods whose envelope is within this fraction of 1.0 are left alone
+    pub drift_tolerance: f64,
+    /// largest correction factor that will be applied, anything larger is likely a sensor fault
+    pub max_correction: f64,
+}
+
+impl Default for SolarQc {
+    fn default() -> Self {
+        SolarQc {
+            rso_tolerance: 1.03,
+            correct_drift: false,
+            period_days: 60,
+            envelope_points: 5,
+            drift_tolerance: 0.02,
+            max_correction: 1.25,
+        }
+    }
+}
+
+/// A rescaling applied to rs over a period of days.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DriftCorrection {
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+    /// mean of the highest Rs/Rso ratios in the period before correction
+    pub envelope: f64,
+    /// factor every rs value in the period was multiplied by
+    pub factor: f64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SolarQcReport {
+    pub flags_added: usize,
+    pub corrections: Vec<DriftCorrection>,
+}
+
+/// Computes clear-sky radiation for every `DailyData` record with rs from the station latitude,
+/// elevation and day of year. When `correct_drift` is set the rs values are rescaled period by
+/// period first, then any value above the clear-sky envelope or below zero is flagged.
+pub fn check_solar(
+    station: &mut StationData,
+    config: &SolarQc,
+) -> Result<SolarQcReport, ClimateError> {
+    let latitude = station.latitude.to_radians();
+    let mut days = Vec::new();
+    for i in date_order(&station.daily_data) {
+        let daily_data = &station.daily_data[i];
+        if let Some(rs) = daily_data.value_in_output_units(Variable::Rs)? {
+            let ra = extraterrestrial_radiation(latitude, daily_data.date.ordinal());
+            let rso = clear_sky_radiation(ra, station.elevation);
+            days.push((i, rs, rso));
+        }
+    }
+
+    let mut report = SolarQcReport::default();
+    if config.correct_drift && config.period_days > 0 && config.envelope_points > 0 {
+        let mut start = 0;
+        while start < days.len() {
+            let period_start = station.daily_data[days[start].0].date;
+            let end = days[start..]
+                .iter()
+                .position(|(i, _, _)| {
+                    (station.daily_data[*i].date - period_start).num_days() >= config.period_days
+                })
+                .map_or(days.len(), |p| start + p);
+
+            let mut ratios: Vec<f64> = days[start..end]
+                .iter()
+                .filter(|(_, _, rso)| *rso > 0.0)
+                .map(|(_, rs, rso)| rs / rso)
+                .collect();
+            ratios.sort_by(|a, b| b.total_cmp(a));
+
+            if ratios.len() >= config.envelope_points {
+                let envelope = ratios[..config.envelope_points].iter().sum::<f64>()
+                    / config.envelope_points as f64;
+                let factor = 1.0 / envelope;
+                if (1.0 - envelope).abs() > config.drift_tolerance
+                    && envelope > 0.0
+                    && factor <= config.max_correction
+                {
+                    for (i, rs, _) in days[start..end].iter_mut() {
+                        let daily_data = &mut station.daily_data[*i];
+                        // every radiation unit conversion is a plain factor, so scaling the value
+                        // in its original units is the same as scaling it in MJ/m²
+                        daily_data.rs = daily_data.rs.map(|value| value * factor);
+                        *rs *= factor;
+                        let message = format!("rs scaled by {:.3} to correct sensor drift", factor);
+                        report.flags_added +=
+                            flag(daily_data, Variable::Rs, FlagKind::Adjusted, message);
+                    }
+
+                    report.corrections.push(DriftCorrection {
+                        start: period_start,
+                        end: station.daily_data[days[end - 1].0].date,
+                        envelope,
+                        factor,
+                    });
+                }
+            }
+            start = end;
+        }
+    }
+
+    for (i, rs, rso) in days {
+        let daily_data = &mut station.daily_data[i];
+        if rs < 0.0 {
+            let message = format!("rs {:.2} MJ/m² is negative", rs);
+            report.flags_added += flag(daily_data, Variable::Rs, FlagKind::OutOfRange, message);
+        } else if rs > rso * config.rso_tolerance {
+            let message = format!(
+                "rs {:.2} MJ/m² is above clear-sky radiation {:.2} MJ/m²",
+                rs, rso
+            );
+            report.flags_added +=
+                flag(daily_data, Variable::Rs, FlagKind::ExceedsClearSky, message);
+        }
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_model::DailyData;
+
+    // rs as a fraction of Rso for each day starting on June 1st
+    fn station_with(rs_ratios: &[f64]) -> StationData {
+        let mut station = StationData::new(
+            "Test".to_string(),
+            "test".to_string(),
+            41.0,
+            -96.0,
+            350.0,
+            2.0,
+            None,
+        );
+        let start = NaiveDate::from_ymd_opt(2023, 6, 1).unwrap();
+        let records = rs_ratios
+            .iter()
+            .enumerate()
+            .map(|(day, ratio)| {
+                let date = start + chrono::Duration::days(day as i64);
+                let ra = extraterrestrial_radiation(41.0_f64.to_radians(), date.ordinal());
+                let rs = clear_sky_radiation(ra, 350.0) * ratio;
+                DailyData::new(
+                    date,
+                    (15.0, "C".to_string()),
+                    (30.0, "C".to_string()),
+                    None,
+                    None,
+                    None,
+                    None,
+                    Some((rs, "MJ/m²".to_string())),
+                    None,
+                    None,
+                )
+                .unwrap()
+            })
+            .collect();
+        station.add_daily_records(records);
+        station
+    }
+
+    #[test]
+    fn flags_values_above_clear_sky() {
+        let mut station = station_with(&[0.95, 1.10, 0.40, 1.01, -0.1]);
+
+        let report = check_solar(&mut station, &SolarQc::default()).unwrap();
+        assert_eq!(report.flags_added, 2);
+        assert!(report.corrections.is_empty());
+        assert_eq!(
+            station.daily_data[1].flags_for(Variable::Rs)[0].kind,
+            FlagKind::ExceedsClearSky
+        );
+        assert!(!station.daily_data[3].is_flagged(Variable::Rs));
+        assert_eq!(
+            station.daily_data[4].flags_for(Variable::Rs)[0].kind,
+            FlagKind::OutOfRange
+        );
+    }
+
+    #[test]
+    fn corrects_drifting_pyranometer() {
+        // the sensor reads 10% low, with a handful of clear days in the period
+        let ratios: Vec<f64> = (0..30)
+            .map(|day| if day % 6 == 0 { 0.9 } else { 0.6 })
+            .collect();
+        let mut station = station_with(&ratios);
+        let before = station.daily_data[1].rs.unwrap();
+
+        let config = SolarQc {
+            correct_drift: true,
+            ..SolarQc::default()
+        };
+        let report = check_solar(&mut station, &config).unwrap();
+
+        assert_eq!(report.corrections.len(), 1);
+        let correction = &report.corrections[0];
+        assert_eq!((correction.envelope * 1000.0).round() / 1000.0, 0.9);
+        assert_eq!(
+            station.daily_data[1].rs.unwrap(),
+            before * correction.factor
+        );
+        assert_eq!(
+            station.daily_data[1].flags_for(Variable::Rs)[0].kind,
+            FlagKind::Adjusted
+        );
+        assert!(!station.daily_data.iter().any(|d| d
+            .flags_for(Variable::Rs)
+            .iter()
+            .any(|f| f.kind == FlagKind::ExceedsClearSky)));
+    }
+}
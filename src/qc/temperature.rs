@@ -0,0 +1,270 @@
+use crate::data_model::{convert_temp_to_c, StationData, Variable};
+use crate::error::ClimateError;
+use crate::qc::{date_order, flag, FlagKind};
+
+/// Thresholds for the temperature checks, all in Celsius.
+#[derive(Debug, Clone)]
+pub struct TemperatureQc {
+    /// lowest plausible daily temperature
+    pub min_c: f64,
+    /// highest plausible daily temperature
+    pub max_c: f64,
+    /// largest change allowed between a day and both of its neighbors before it is a spike
+    pub max_daily_change_c: f64,
+    /// number of consecutive days with an identical value that marks a flat-lined sensor
+    pub flat_line_days: usize,
+}
+
+impl Default for TemperatureQc {
+    fn default() -> Self {
+        TemperatureQc {
+            min_c: -50.0,
+            max_c: 55.0,
+            max_daily_change_c: 15.0,
+            flat_line_days: 5,
+        }
+    }
+}
+
+/// Runs the temperature checks over every `DailyData` record in the station and flags tmax < tmin
+/// inversions, values outside of `min_c`..`max_c`, spikes and flat-lined sensors on tmin and tmax.
+/// Returns the number of new flags that were added.
+pub fn check_temperature(
+    station: &mut StationData,
+    config: &TemperatureQc,
+) -> Result<usize, ClimateError> {
+    let order = date_order(&station.daily_data);
+    let mut tmin = Vec::with_capacity(order.len());
+    let mut tmax = Vec::with_capacity(order.len());
+    for i in &order {
+        let daily_data = &station.daily_data[*i];
+        tmin.push(
+            convert_temp_to_c(daily_data.tmin, &daily_data.tmin_units)
+                .map_err(|e| e.with_context(Variable::Tmin.name(), daily_data.date))?,
+        );
+        tmax.push(
+            convert_temp_to_c(daily_data.tmax, &daily_data.tmax_units)
+                .map_err(|e| e.with_context(Variable::Tmax.name(), daily_data.date))?,
+        );
+    }
+
+    let mut added = 0;
+    for (position, i) in order.iter().enumerate() {
+        if tmax[position] < tmin[position] {
+            let message = format!(
+                "tmax {:.1} °C is below tmin {:.1} °C",
+                tmax[position], tmin[position]
+            );
+            let daily_data = &mut station.daily_data[*i];
+            added += flag(
+                daily_data,
+                Variable::Tmin,
+                FlagKind::Inversion,
+                message.clone(),
+            );
+            added += flag(daily_data, Variable::Tmax, FlagKind::Inversion, message);
+        }
+    }
+
+    for (variable, values) in [(Variable::Tmin, &tmin), (Variable::Tmax, &tmax)] {
+        // a neighbor only counts when it is the next or previous calendar day
+        let consecutive: Vec<bool> = (0..order.len())
+            .map(|p| {
+                p > 0
+                    && (station.daily_data[order[p]].date - station.daily_data[order[p - 1]].date)
+                        .num_days()
+                        == 1
+            })
+            .collect();
+
+        for (position, i) in order.iter().enumerate() {
+            let value = values[position];
+            if value < config.min_c || value > config.max_c {
+                let message = format!(
+                    "{} {:.1} °C is outside of {:.1} to {:.1} °C",
+                    variable.name(),
+                    value,
+                    config.min_c,
+                    config.max_c
+                );
+                added += flag(
+                    &mut station.daily_data[*i],
+                    variable,
+                    FlagKind::OutOfRange,
+                    message,
+                );
+            }
+
+            if position > 0
+                && position + 1 < order.len()
+                && consecutive[position]
+                && consecutive[position + 1]
+            {
+                let before = value - values[position - 1];
+                let after = value - values[position + 1];
+                if before.abs() > config.max_daily_change_c
+                    && after.abs() > config.max_daily_change_c
+                    && before.signum() == after.signum()
+                {
+                    let message = format!(
+                        "{} {:.1} °C differs from both neighboring days by more than {:.1} °C",
+                        variable.name(),
+                        value,
+                        config.max_daily_change_c
+                    );
+                    added += flag(
+                        &mut station.daily_data[*i],
+                        variable,
+                        FlagKind::Spike,
+                        message,
+                    );
+                }
+            }
+        }
+
+        if config.flat_line_days < 2 {
+            continue;
+        }
+
+        let mut run_start = 0;
+        for position in 1..=order.len() {
+            let continues = position < order.len()
+                && consecutive[position]
+                && values[position] == values[position - 1];
+            if continues {
+                continue;
+            }
+
+            let run_length = position - run_start;
+            if run_length >= config.flat_line_days {
+                for i in &order[run_start..position] {
+                    let message = format!(
+                        "{} repeated {:.1} °C for {} days",
+                        variable.name(),
+                        values[run_start],
+                        run_length
+                    );
+                    added += flag(
+                        &mut station.daily_data[*i],
+                        variable,
+                        FlagKind::FlatLine,
+                        message,
+                    );
+                }
+            }
+            run_start = position;
+        }
+    }
+
+    Ok(added)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_model::DailyData;
+    use chrono::NaiveDate;
+
+    fn station_with(temperatures: &[(f64, f64)]) -> StationData {
+        let mut station = StationData::new(
+            "Test".to_string(),
+            "test".to_string(),
+            41.0,
+            -96.0,
+            350.0,
+            2.0,
+            None,
+        );
+        let start = NaiveDate::from_ymd_opt(2023, 6, 1).unwrap();
+        let records = temperatures
+            .iter()
+            .enumerate()
+            .map(|(day, (tmin, tmax))| {
+                DailyData::new(
+                    start + chrono::Duration::days(day as i64),
+                    (*tmin, "°F".to_string()),
+                    (*tmax, "°F".to_string()),
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                )
+                .unwrap()
+            })
+            .collect();
+        station.add_daily_records(records);
+        station
+    }
+
+    #[test]
+    fn flags_inversion_extremes_and_spikes() {
+        let mut station = station_with(&[
+            (60.0, 85.0),
+            (62.0, 58.0),
+            (61.0, 86.0),
+            (61.5, 160.0),
+            (60.5, 87.0),
+        ]);
+
+        let added = check_temperature(&mut station, &TemperatureQc::default()).unwrap();
+        assert_eq!(added, 4);
+
+        let day = &station.daily_data[1];
+        assert_eq!(day.flags_for(Variable::Tmin)[0].kind, FlagKind::Inversion);
+        assert_eq!(day.flags_for(Variable::Tmax)[0].kind, FlagKind::Inversion);
+
+        let kinds: Vec<FlagKind> = station.daily_data[3]
+            .flags_for(Variable::Tmax)
+            .iter()
+            .map(|f| f.kind)
+            .collect();
+        assert_eq!(kinds, vec![FlagKind::OutOfRange, FlagKind::Spike]);
+        assert!(!station.daily_data[3].is_flagged(Variable::Tmin));
+        assert_eq!(station.daily_data.len(), 5);
+
+        // running the check again doesn't duplicate flags
+        assert_eq!(
+            check_temperature(&mut station, &TemperatureQc::default()).unwrap(),
+            0
+        );
+    }
+
+    #[test]
+    fn flags_flat_lined_sensor() {
+        let mut station = station_with(&[
+            (50.0, 80.0),
+            (51.0, 82.0),
+            (51.0, 84.0),
+            (51.0, 81.0),
+            (51.0, 79.0),
+            (51.0, 83.0),
+            (49.0, 80.0),
+        ]);
+
+        check_temperature(&mut station, &TemperatureQc::default()).unwrap();
+
+        let flagged: Vec<bool> = station
+            .daily_data
+            .iter()
+            .map(|d| d.is_flagged(Variable::Tmin))
+            .collect();
+        assert_eq!(flagged, vec![false, true, true, true, true, true, false]);
+        assert_eq!(crate::qc::flagged_count(&station, Variable::Tmax), 0);
+    }
+
+    #[test]
+    fn bad_units_name_the_variable_and_day() {
+        let mut station = station_with(&[(60.0, 85.0), (62.0, 86.0)]);
+        station.daily_data[1].tmax_units = "furlongs".to_string();
+
+        let error = check_temperature(&mut station, &TemperatureQc::default()).unwrap_err();
+        assert_eq!(error.variable(), Some("tmax"));
+        assert_eq!(
+            error.date(),
+            Some(NaiveDate::from_ymd_opt(2023, 6, 2).unwrap())
+        );
+    }
+}
@@ -0,0 +1,472 @@
+//! Typed physical quantities built on `Units`.
+//!
+//! Each kind of quantity has its own units enum holding only the `Units` of that kind, so a
+//! `Speed` can't be given a temperature unit and a conversion can't cross kinds. Values read with
+//! their units as text, as the readers and `DailyData` hold them, are checked once in
+//! `Quantity::parse`.
+
+use crate::error::ClimateError;
+use crate::units::{Period, Units};
+use std::fmt;
+
+/// The units of one kind of quantity.
+pub trait QuantityUnits:
+    Copy + PartialEq + fmt::Debug + Into<Units> + TryFrom<Units, Error = ClimateError>
+{
+}
+
+/// A value with its units.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quantity<U> {
+    pub value: f64,
+    pub units: U,
+}
+
+pub type Temperature = Quantity<TemperatureUnits>;
+pub type TemperatureDifference = Quantity<TemperatureDifferenceUnits>;
+pub type Length = Quantity<LengthUnits>;
+pub type Speed = Quantity<SpeedUnits>;
+pub type Pressure = Quantity<PressureUnits>;
+pub type RadiantExposure = Quantity<RadiantExposureUnits>;
+pub type Ratio = Quantity<RatioUnits>;
+
+impl<U: QuantityUnits> Quantity<U> {
+    pub fn new(value: f64, units: U) -> Quantity<U> {
+        Quantity { value, units }
+    }
+
+    /// Reads a value with its units abbreviation, the units must be of this kind of quantity.
+    pub fn parse(value: f64, units: &str) -> Result<Quantity<U>, ClimateError> {
+        Ok(Quantity {
+            value,
+            units: U::try_from(Units::from_abbreviation(units)?)?,
+        })
+    }
+
+    /// Converts to other units of the same kind.
+    pub fn convert(&self, to: U) -> Result<Quantity<U>, ClimateError> {
+        if self.units == to {
+            return Ok(*self);
+        }
+        let from: Units = self.units.into();
+        Ok(Quantity {
+            value: from.convert(self.value, &to.into())?,
+            units: to,
+        })
+    }
+
+    /// The value in other units of the same kind.
+    pub fn value_in(&self, units: U) -> Result<f64, ClimateError> {
+        self.convert(units).map(|quantity| quantity.value)
+    }
+
+    pub fn abbreviation(&self) -> String {
+        let units: Units = self.units.into();
+        units.to_abbreviation().to_string()
+    }
+}
+
+/// The value and units abbreviation, as `DailyData::new` takes them.
+impl<U: QuantityUnits> From<Quantity<U>> for (f64, String) {
+    fn from(quantity: Quantity<U>) -> (f64, String) {
+        (quantity.value, quantity.abbreviation())
+    }
+}
+
+impl<U: QuantityUnits> fmt::Display for Quantity<U> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.value, self.abbreviation())
+    }
+}
+
+/// The error for units of another kind, naming the units values of this kind are held in.
+fn wrong_kind(units: Units, expected: Units) -> ClimateError {
+    ClimateError::unsupported_conversion(units.name(), expected.name())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TemperatureUnits {
+    Celsius,
+    Fahrenheit,
+    Kelvin,
+}
+
+impl From<TemperatureUnits> for Units {
+    fn from(units: TemperatureUnits) -> Units {
+        match units {
+            TemperatureUnits::Celsius => Units::Celsius,
+            TemperatureUnits::Fahrenheit => Units::Fahrenheit,
+            TemperatureUnits::Kelvin => Units::Kelvin,
+        }
+    }
+}
+
+impl TryFrom<Units> for TemperatureUnits {
+    type Error = ClimateError;
+
+    fn try_from(units: Units) -> Result<TemperatureUnits, ClimateError> {
+        match units {
+            Units::Celsius => Ok(TemperatureUnits::Celsius),
+            Units::Fahrenheit => Ok(TemperatureUnits::Fahrenheit),
+            Units::Kelvin => Ok(TemperatureUnits::Kelvin),
+            units => Err(wrong_kind(units, Units::Celsius)),
+        }
+    }
+}
+
+impl QuantityUnits for TemperatureUnits {}
+
+/// Units of a change in temperature, kept apart from `TemperatureUnits` so an offset or a lapse
+/// rate is never shifted by the zero point of a scale.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TemperatureDifferenceUnits {
+    Celsius,
+    Fahrenheit,
+    Kelvin,
+}
+
+impl From<TemperatureDifferenceUnits> for Units {
+    fn from(units: TemperatureDifferenceUnits) -> Units {
+        match units {
+            TemperatureDifferenceUnits::Celsius => Units::CelsiusDifference,
+            TemperatureDifferenceUnits::Fahrenheit => Units::FahrenheitDifference,
+            TemperatureDifferenceUnits::Kelvin => Units::KelvinDifference,
+        }
+    }
+}
+
+impl TryFrom<Units> for TemperatureDifferenceUnits {
+    type Error = ClimateError;
+
+    fn try_from(units: Units) -> Result<TemperatureDifferenceUnits, ClimateError> {
+        match units {
+            Units::CelsiusDifference => Ok(TemperatureDifferenceUnits::Celsius),
+            Units::FahrenheitDifference => Ok(TemperatureDifferenceUnits::Fahrenheit),
+            Units::KelvinDifference => Ok(TemperatureDifferenceUnits::Kelvin),
+            units => Err(wrong_kind(units, Units::CelsiusDifference)),
+        }
+    }
+}
+
+impl TemperatureDifference {
+    /// The difference in Celsius degrees, which every temperature difference converts to.
+    pub fn celsius(&self) -> f64 {
+        Units::from(self.units).base_value(self.value)
+    }
+}
+
+impl QuantityUnits for TemperatureDifferenceUnits {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LengthUnits {
+    Millimeters,
+    Centimeters,
+    Meters,
+    Kilometers,
+    Inches,
+    Feet,
+    Yards,
+    Miles,
+}
+
+impl From<LengthUnits> for Units {
+    fn from(units: LengthUnits) -> Units {
+        match units {
+            LengthUnits::Millimeters => Units::Millimeters,
+            LengthUnits::Centimeters => Units::Centimeters,
+            LengthUnits::Meters => Units::Meters,
+            LengthUnits::Kilometers => Units::Kilometers,
+            LengthUnits::Inches => Units::Inches,
+            LengthUnits::Feet => Units::Feet,
+            LengthUnits::Yards => Units::Yards,
+            LengthUnits::Miles => Units::Miles,
+        }
+    }
+}
+
+impl TryFrom<Units> for LengthUnits {
+    type Error = ClimateError;
+
+    fn try_from(units: Units) -> Result<LengthUnits, ClimateError> {
+        match units {
+            Units::Millimeters => Ok(LengthUnits::Millimeters),
+            Units::Centimeters => Ok(LengthUnits::Centimeters),
+            Units::Meters => Ok(LengthUnits::Meters),
+            Units::Kilometers => Ok(LengthUnits::Kilometers),
+            Units::Inches => Ok(LengthUnits::Inches),
+            Units::Feet => Ok(LengthUnits::Feet),
+            Units::Yards => Ok(LengthUnits::Yards),
+            Units::Miles => Ok(LengthUnits::Miles),
+            units => Err(wrong_kind(units, Units::Millimeters)),
+        }
+    }
+}
+
+impl QuantityUnits for LengthUnits {}
+
+/// Speed units, including wind run totals per day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpeedUnits {
+    MetersPerSecond,
+    MilesPerHour,
+    KilometersPerHour,
+    FeetPerSecond,
+    Knots,
+    KilometersPerDay,
+    MilesPerDay,
+}
+
+impl From<SpeedUnits> for Units {
+    fn from(units: SpeedUnits) -> Units {
+        match units {
+            SpeedUnits::MetersPerSecond => Units::MetersPerSecond,
+            SpeedUnits::MilesPerHour => Units::MilesPerHour,
+            SpeedUnits::KilometersPerHour => Units::KilometersPerHour,
+            SpeedUnits::FeetPerSecond => Units::FeetPerSecond,
+            SpeedUnits::Knots => Units::Knots,
+            SpeedUnits::KilometersPerDay => Units::KilometersPerDay,
+            SpeedUnits::MilesPerDay => Units::MilesPerDay,
+        }
+    }
+}
+
+impl TryFrom<Units> for SpeedUnits {
+    type Error = ClimateError;
+
+    fn try_from(units: Units) -> Result<SpeedUnits, ClimateError> {
+        match units {
+            Units::MetersPerSecond => Ok(SpeedUnits::MetersPerSecond),
+            Units::MilesPerHour => Ok(SpeedUnits::MilesPerHour),
+            Units::KilometersPerHour => Ok(SpeedUnits::KilometersPerHour),
+            Units::FeetPerSecond => Ok(SpeedUnits::FeetPerSecond),
+            Units::Knots => Ok(SpeedUnits::Knots),
+            Units::KilometersPerDay => Ok(SpeedUnits::KilometersPerDay),
+            Units::MilesPerDay => Ok(SpeedUnits::MilesPerDay),
+            units => Err(wrong_kind(units, Units::MetersPerSecond)),
+        }
+    }
+}
+
+impl QuantityUnits for SpeedUnits {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PressureUnits {
+    Pascals,
+    KiloPascals,
+    Hectopascals,
+    Millibars,
+    InchesOfMercury,
+    MillimetersOfMercury,
+}
+
+impl From<PressureUnits> for Units {
+    fn from(units: PressureUnits) -> Units {
+        match units {
+            PressureUnits::Pascals => Units::Pascals,
+            PressureUnits::KiloPascals => Units::KiloPascals,
+            PressureUnits::Hectopascals => Units::Hectopascals,
+            PressureUnits::Millibars => Units::Millibars,
+            PressureUnits::InchesOfMercury => Units::InchesOfMercury,
+            PressureUnits::MillimetersOfMercury => Units::MillimetersOfMercury,
+        }
+    }
+}
+
+impl TryFrom<Units> for PressureUnits {
+    type Error = ClimateError;
+
+    fn try_from(units: Units) -> Result<PressureUnits, ClimateError> {
+        match units {
+            Units::Pascals => Ok(PressureUnits::Pascals),
+            Units::KiloPascals => Ok(PressureUnits::KiloPascals),
+            Units::Hectopascals => Ok(PressureUnits::Hectopascals),
+            Units::Millibars => Ok(PressureUnits::Millibars),
+            Units::InchesOfMercury => Ok(PressureUnits::InchesOfMercury),
+            Units::MillimetersOfMercury => Ok(PressureUnits::MillimetersOfMercury),
+            units => Err(wrong_kind(units, Units::KiloPascals)),
+        }
+    }
+}
+
+impl QuantityUnits for PressureUnits {}
+
+/// Solar radiation, either as a total or as an average flux. A total in MJ/m² or Langleys
+/// without a period has to be given one with `RadiantExposure::over` before it can be converted
+/// to a flux or to a total over a period.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RadiantExposureUnits {
+    MegaJoulesPerSquareMeter,
+    Langley,
+    WattsPerSquareMeter,
+    MegaJoulesPerSquareMeterPerDay,
+    MegaJoulesPerSquareMeterPerHour,
+    LangleysPerDay,
+    LangleysPerHour,
+}
+
+impl From<RadiantExposureUnits> for Units {
+    fn from(units: RadiantExposureUnits) -> Units {
+        match units {
+            RadiantExposureUnits::MegaJoulesPerSquareMeter => Units::MegaJoulesPerSquareMeter,
+            RadiantExposureUnits::Langley => Units::Langley,
+            RadiantExposureUnits::WattsPerSquareMeter => Units::WattsPerSquareMeter,
+            RadiantExposureUnits::MegaJoulesPerSquareMeterPerDay => {
+                Units::MegaJoulesPerSquareMeterPerDay
+            }
+            RadiantExposureUnits::MegaJoulesPerSquareMeterPerHour => {
+                Units::MegaJoulesPerSquareMeterPerHour
+            }
+            RadiantExposureUnits::LangleysPerDay => Units::LangleysPerDay,
+            RadiantExposureUnits::LangleysPerHour => Units::LangleysPerHour,
+        }
+    }
+}
+
+impl TryFrom<Units> for RadiantExposureUnits {
+    type Error = ClimateError;
+
+    fn try_from(units: Units) -> Result<RadiantExposureUnits, ClimateError> {
+        match units {
+            Units::MegaJoulesPerSquareMeter => Ok(RadiantExposureUnits::MegaJoulesPerSquareMeter),
+            Units::Langley => Ok(RadiantExposureUnits::Langley),
+            Units::WattsPerSquareMeter => Ok(RadiantExposureUnits::WattsPerSquareMeter),
+            Units::MegaJoulesPerSquareMeterPerDay => {
+                Ok(RadiantExposureUnits::MegaJoulesPerSquareMeterPerDay)
+            }
+            Units::MegaJoulesPerSquareMeterPerHour => {
+                Ok(RadiantExposureUnits::MegaJoulesPerSquareMeterPerHour)
+            }
+            Units::LangleysPerDay => Ok(RadiantExposureUnits::LangleysPerDay),
+            Units::LangleysPerHour => Ok(RadiantExposureUnits::LangleysPerHour),
+            units => Err(wrong_kind(units, Units::MegaJoulesPerSquareMeterPerDay)),
+        }
+    }
+}
+
+impl RadiantExposure {
+    /// Reads a total without a period as accumulated over `period`, see `Units::over`.
+    pub fn over(&self, period: Period) -> RadiantExposure {
+        let units: Units = self.units.into();
+        match RadiantExposureUnits::try_from(units.over(period)) {
+            Ok(units) => RadiantExposure::new(self.value, units),
+            Err(_) => *self,
+        }
+    }
+}
+
+impl QuantityUnits for RadiantExposureUnits {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RatioUnits {
+    Percent,
+}
+
+impl From<RatioUnits> for Units {
+    fn from(units: RatioUnits) -> Units {
+        match units {
+            RatioUnits::Percent => Units::Percent,
+        }
+    }
+}
+
+impl TryFrom<Units> for RatioUnits {
+    type Error = ClimateError;
+
+    fn try_from(units: Units) -> Result<RatioUnits, ClimateError> {
+        match units {
+            Units::Percent => Ok(RatioUnits::Percent),
+            units => Err(wrong_kind(units, Units::Percent)),
+        }
+    }
+}
+
+impl QuantityUnits for RatioUnits {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_within_a_kind() {
+        let tmax = Temperature::parse(77.0, "°F").unwrap();
+        assert_eq!(tmax.units, TemperatureUnits::Fahrenheit);
+        assert_eq!(tmax.value_in(TemperatureUnits::Celsius), Ok(25.0));
+        assert_eq!(tmax.to_string(), "77 °F");
+
+        let precip = Length::new(0.5, LengthUnits::Inches);
+        assert_eq!(
+            precip.convert(LengthUnits::Millimeters),
+            Ok(Length::new(12.7, LengthUnits::Millimeters))
+        );
+        assert_eq!(precip.value_in(LengthUnits::Inches), Ok(0.5));
+
+        let wind_run = Speed::parse(172.8, "km/day").unwrap();
+        assert_eq!(wind_run.units, SpeedUnits::KilometersPerDay);
+        let ws = wind_run.value_in(SpeedUnits::MetersPerSecond).unwrap();
+        assert!((ws - 2.0).abs() < 1e-6);
+
+        let pair: (f64, String) = Pressure::new(1.2, PressureUnits::KiloPascals).into();
+        assert_eq!(pair, (1.2, "kPa".to_string()));
+    }
+
+    #[test]
+    fn radiation_is_converted_over_its_period() {
+        let rs = RadiantExposure::parse(25.92, "MJ/m²").unwrap();
+        assert!(rs
+            .value_in(RadiantExposureUnits::WattsPerSquareMeter)
+            .is_err());
+
+        let daily = rs.over(Period::Day);
+        assert_eq!(
+            daily.units,
+            RadiantExposureUnits::MegaJoulesPerSquareMeterPerDay
+        );
+        assert_eq!(
+            daily.value_in(RadiantExposureUnits::WattsPerSquareMeter),
+            Ok(300.0)
+        );
+        let hourly = rs.over(Period::Hour);
+        assert_eq!(
+            hourly.value_in(RadiantExposureUnits::WattsPerSquareMeter),
+            Ok(7200.0)
+        );
+
+        // a daily mean flux of 300 W/m² is 25.92 MJ/m² a day or 1.08 MJ/m² each hour
+        let flux = RadiantExposure::new(300.0, RadiantExposureUnits::WattsPerSquareMeter);
+        let round = |value: f64| (value * 1e9).round() / 1e9;
+        assert_eq!(
+            flux.value_in(RadiantExposureUnits::MegaJoulesPerSquareMeterPerDay)
+                .map(round),
+            Ok(25.92)
+        );
+        assert_eq!(
+            flux.value_in(RadiantExposureUnits::MegaJoulesPerSquareMeterPerHour)
+                .map(round),
+            Ok(1.08)
+        );
+        let langleys = RadiantExposure::parse(600.0, "L")
+            .unwrap()
+            .over(Period::Day);
+        assert_eq!(
+            langleys
+                .value_in(RadiantExposureUnits::MegaJoulesPerSquareMeterPerDay)
+                .map(round),
+            Ok(25.104)
+        );
+    }
+
+    #[test]
+    fn units_of_another_kind_are_rejected() {
+        assert_eq!(
+            Speed::parse(2.0, "°C"),
+            Err(ClimateError::unsupported_conversion(
+                "Celsius",
+                "Meters/Second"
+            ))
+        );
+        assert_eq!(
+            Ratio::parse(50.0, "furlongs"),
+            Err(ClimateError::unknown_unit("furlongs"))
+        );
+    }
+}
@@ -0,0 +1,343 @@
+//! Reads daily records for a single station from a CSV file using a column and unit mapping.
+
+use crate::data_model::{convert_to_output_units, DailyData, StationData, Variable};
+use crate::database::ColumnMapping;
+use crate::error::ClimateError;
+use chrono::NaiveDate;
+use csv::{ReaderBuilder, StringRecord, Trim};
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// Describes which columns of a CSV file hold each variable and the units they are in. Column
+/// names are matched against the header row, units are any string accepted by
+/// `Units::from_abbreviation`. Optional variables that are `None` are left empty on the
+/// `DailyData`.
+#[derive(Debug, Clone)]
+pub struct CsvMapping {
+    pub date_column: String,
+    /// `chrono` format string for the date column, e.g. `%m/%d/%Y`
+    pub date_format: String,
+    pub delimiter: u8,
+    /// cell values that mean the value is missing, an empty cell is always missing
+    pub missing_values: Vec<String>,
+    pub tmin: ColumnMapping,
+    pub tmax: ColumnMapping,
+    pub rhmin: Option<ColumnMapping>,
+    pub rhmax: Option<ColumnMapping>,
+    pub dewpoint: Option<ColumnMapping>,
+    pub precip: Option<ColumnMapping>,
+    pub rs: Option<ColumnMapping>,
+    pub ea: Option<ColumnMapping>,
+    pub wind_speed: Option<ColumnMapping>,
+    pub pressure: Option<ColumnMapping>,
+}
+
+impl Default for CsvMapping {
+    fn default() -> Self {
+        CsvMapping {
+            date_column: "date".to_string(),
+            date_format: "%Y-%m-%d".to_string(),
+            delimiter: b',',
+            missing_values: vec!["NA".to_string(), "NaN".to_string(), "-9999".to_string()],
+            tmin: ColumnMapping::new("tmin", "°C"),
+            tmax: ColumnMapping::new("tmax", "°C"),
+            rhmin: Some(ColumnMapping::new("rhmin", "%")),
+            rhmax: Some(ColumnMapping::new("rhmax", "%")),
+            dewpoint: Some(ColumnMapping::new("dewpoint", "°C")),
+            precip: Some(ColumnMapping::new("precip", "mm")),
+            rs: Some(ColumnMapping::new("rs", "MJ/m²")),
+            ea: Some(ColumnMapping::new("ea", "kPa")),
+            wind_speed: Some(ColumnMapping::new("wind_speed", "m/s")),
+            pressure: None,
+        }
+    }
+}
+
+impl CsvMapping {
+    /// The mapped variables in the order of the `DailyData::new` arguments, then pressure.
+    fn variables(&self) -> Vec<(Variable, Option<&ColumnMapping>)> {
+        vec![
+            (Variable::Tmin, Some(&self.tmin)),
+            (Variable::Tmax, Some(&self.tmax)),
+            (Variable::RhMin, self.rhmin.as_ref()),
+            (Variable::RhMax, self.rhmax.as_ref()),
+            (Variable::Dewpoint, self.dewpoint.as_ref()),
+            (Variable::Precip, self.precip.as_ref()),
+            (Variable::Rs, self.rs.as_ref()),
+            (Variable::Ea, self.ea.as_ref()),
+            (Variable::WindSpeed, self.wind_speed.as_ref()),
+            (Variable::Pressure, self.pressure.as_ref()),
+        ]
+    }
+}
+
+/// The result of reading a file. Rows that could not be read are skipped and every problem
+/// found in them is kept in `errors` with its row and column.
+#[derive(Debug)]
+pub struct CsvReport {
+    pub records_added: usize,
+    pub errors: Vec<ClimateError>,
+}
+
+/// Reads a CSV file and adds its daily records to `station`.
+pub fn read_csv_file(
+    path: impl AsRef<Path>,
+    station: &mut StationData,
+    mapping: &CsvMapping,
+) -> Result<CsvReport, ClimateError> {
+    let path = path.as_ref();
+    let file = File::open(path)
+        .map_err(|e| ClimateError::Io(format!("Could not open {}: {}", path.display(), e)))?;
+    read_csv(file, station, mapping)
+}
+
+/// Reads CSV data with a header row and adds its daily records to `station`.
+///
+/// The mapping is checked before any rows are read, so unknown units, units that don't fit the
+/// variable and columns missing from the header are returned as an error. After that a bad row
+/// never stops the read; rows missing tmin or tmax, with an unparseable date or value, or that
+/// the CSV reader can't split are reported in `CsvReport::errors` and skipped. Row numbers are
+/// line numbers in the file, with the header on row 1.
+pub fn read_csv<R: Read>(
+    reader: R,
+    station: &mut StationData,
+    mapping: &CsvMapping,
+) -> Result<CsvReport, ClimateError> {
+    for (variable, column) in mapping.variables() {
+        if let Some(column) = column {
+            convert_to_output_units(variable, 0.0, &column.units)
+                .map_err(|e| e.with_variable(variable.name()))?;
+        }
+    }
+
+    let mut csv_reader = ReaderBuilder::new()
+        .delimiter(mapping.delimiter)
+        .trim(Trim::All)
+        .flexible(true)
+        .from_reader(reader);
+    let headers = csv_reader
+        .headers()
+        .map_err(|e| ClimateError::parse(e.to_string(), Some(1), None))?
+        .clone();
+    let index = |column: &str| {
+        headers.iter().position(|h| h == column).ok_or_else(|| {
+            ClimateError::parse(
+                "Column not found in header".to_string(),
+                Some(1),
+                Some(column),
+            )
+        })
+    };
+
+    let date_index = index(&mapping.date_column)?;
+    let mut columns = Vec::new();
+    for (variable, column) in mapping.variables() {
+        if let Some(column) = column {
+            columns.push((variable, column, index(&column.column)?));
+        }
+    }
+
+    let mut report = CsvReport {
+        records_added: 0,
+        errors: Vec::new(),
+    };
+    for record in csv_reader.records() {
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                let row = e.position().map(|p| p.line() as usize);
+                report
+                    .errors
+                    .push(ClimateError::parse(e.to_string(), row, None));
+                continue;
+            }
+        };
+        let row = record.position().map(|p| p.line() as usize);
+        let mut row_errors = Vec::new();
+
+        let date_cell = record.get(date_index).unwrap_or("");
+        let date = NaiveDate::parse_from_str(date_cell, &mapping.date_format)
+            .map_err(|e| {
+                row_errors.push(ClimateError::parse(
+                    format!("Invalid date '{}': {}", date_cell, e),
+                    row,
+                    Some(&mapping.date_column),
+                ))
+            })
+            .ok();
+
+        let mut values: [Option<(f64, String)>; 10] = Default::default();
+        for (variable, column, index) in &columns {
+            match cell(&record, *index, mapping) {
+                Ok(value) => {
+                    values[position(*variable)] = value.map(|value| (value, column.units.clone()))
+                }
+                Err(message) => {
+                    row_errors.push(ClimateError::parse(message, row, Some(&column.column)))
+                }
+            }
+        }
+        for (variable, column) in [
+            (Variable::Tmin, &mapping.tmin.column),
+            (Variable::Tmax, &mapping.tmax.column),
+        ] {
+            let index = position(variable);
+            if values[index].is_none() && !row_errors.iter().any(|e| in_column(e, column)) {
+                row_errors.push(ClimateError::parse(
+                    format!("{} is required", variable.name()),
+                    row,
+                    Some(column),
+                ));
+            }
+        }
+
+        let date = match date {
+            Some(date) if row_errors.is_empty() => date,
+            _ => {
+                report.errors.append(&mut row_errors);
+                continue;
+            }
+        };
+        let [tmin, tmax, rhmin, rhmax, dewpoint, precip, rs, ea, wind_speed, pressure] = values;
+        let daily_data = DailyData::new(
+            date,
+            tmin.unwrap(),
+            tmax.unwrap(),
+            rhmin,
+            rhmax,
+            dewpoint,
+            precip,
+            rs,
+            ea,
+            wind_speed,
+        )
+        .and_then(|daily_data| match pressure {
+            Some(pressure) => daily_data.with_pressure(pressure),
+            None => Ok(daily_data),
+        });
+        match daily_data {
+            Ok(daily_data) => {
+                station.daily_data.push(daily_data);
+                report.records_added += 1;
+            }
+            Err(e) => report.errors.push(e),
+        }
+    }
+
+    Ok(report)
+}
+
+/// Parses a single cell, `Ok(None)` when it is empty or one of the missing values.
+fn cell(record: &StringRecord, index: usize, mapping: &CsvMapping) -> Result<Option<f64>, String> {
+    let value = record.get(index).unwrap_or("");
+    if value.is_empty() || mapping.missing_values.iter().any(|m| m == value) {
+        return Ok(None);
+    }
+    value
+        .parse::<f64>()
+        .map(Some)
+        .map_err(|_| format!("Invalid number '{}'", value))
+}
+
+/// Index of a variable in the order of `CsvMapping::variables`.
+fn position(variable: Variable) -> usize {
+    Variable::all().iter().position(|v| *v == variable).unwrap()
+}
+
+fn in_column(error: &ClimateError, name: &str) -> bool {
+    matches!(error, ClimateError::Parse { column: Some(column), .. } if column == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn station() -> StationData {
+        StationData::new(
+            "Test".to_string(),
+            "csv".to_string(),
+            41.0,
+            -100.0,
+            900.0,
+            2.0,
+            None,
+        )
+    }
+
+    #[test]
+    fn reads_rows_and_reports_bad_cells() {
+        let data = "\
+Date,Min F,Max F,Rain,Solar,Wind,Baro
+07/01/2023,59,86,0.1,620,8,972
+07/02/2023,61,x,0,600,9,971
+07/03/2023,,88,NA,610,,970
+07/04/2023,60,89,,NA,7,
+2023-07-05,60,89,0,600,7,969
+";
+        let mapping = CsvMapping {
+            date_column: "Date".to_string(),
+            date_format: "%m/%d/%Y".to_string(),
+            tmin: ColumnMapping::new("Min F", "F"),
+            tmax: ColumnMapping::new("Max F", "F"),
+            rhmin: None,
+            rhmax: None,
+            dewpoint: None,
+            precip: Some(ColumnMapping::new("Rain", "in")),
+            rs: Some(ColumnMapping::new("Solar", "L")),
+            ea: None,
+            wind_speed: Some(ColumnMapping::new("Wind", "mph")),
+            pressure: Some(ColumnMapping::new("Baro", "mbar")),
+            ..CsvMapping::default()
+        };
+
+        let mut station = station();
+        let report = read_csv(data.as_bytes(), &mut station, &mapping).unwrap();
+        assert_eq!(report.records_added, 2);
+        assert_eq!(station.daily_data.len(), 2);
+        assert_eq!(station.daily_data[0].precip, Some(0.1));
+        assert_eq!(station.daily_data[1].rs, None);
+        assert_eq!(station.daily_data[0].pressure, Some(972.0));
+        assert_eq!(station.daily_data[1].pressure, None);
+        let output = station.to_output().unwrap();
+        assert!((output[0].get_pressure().unwrap() - 97.2).abs() < 1e-9);
+
+        let errors: Vec<String> = report.errors.iter().map(|e| e.to_string()).collect();
+        assert_eq!(errors.len(), 3);
+        assert_eq!(errors[0], "Invalid number 'x' at row 3 in column Max F");
+        assert_eq!(errors[1], "tmin is required at row 4 in column Min F");
+        assert!(errors[2].starts_with("Invalid date '2023-07-05'"));
+        assert!(errors[2].ends_with("at row 6 in column Date"));
+    }
+
+    #[test]
+    fn bad_mapping_is_an_error() {
+        let data = "date,tmin,tmax\n2023-07-01,15,30\n";
+        let mut station = station();
+
+        let mapping = CsvMapping {
+            rhmin: None,
+            rhmax: None,
+            dewpoint: None,
+            precip: None,
+            rs: None,
+            ea: None,
+            wind_speed: Some(ColumnMapping::new("wind", "m/s")),
+            ..CsvMapping::default()
+        };
+        let error = read_csv(data.as_bytes(), &mut station, &mapping).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Column not found in header at row 1 in column wind"
+        );
+
+        let mapping = CsvMapping {
+            tmin: ColumnMapping::new("tmin", "kPa"),
+            wind_speed: None,
+            ..mapping
+        };
+        let error = read_csv(data.as_bytes(), &mut station, &mapping).unwrap_err();
+        assert_eq!(error.variable(), Some("tmin"));
+        assert!(station.daily_data.is_empty());
+    }
+}
@@ -0,0 +1,417 @@
+//! Reads NOAA GHCN-Daily `.dly` files and `ghcnd-stations.txt` station metadata.
+//!
+//! Each `.dly` line holds one element for one month: the station id in columns 1-11, the year in
+//! 12-15, the month in 16-17, the element in 18-21 and then 31 groups of a five character value
+//! and the measurement, quality and source flags. Temperatures are in tenths of a degree Celsius,
+//! precipitation in tenths of a mm and wind speed in tenths of a m/s, -9999 is missing.
+
+use crate::data_model::{DailyData, StationData, Variable};
+use crate::error::ClimateError;
+use crate::qc::{flag, FlagKind};
+use chrono::NaiveDate;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+
+/// The value GHCN-Daily uses for a missing day.
+const MISSING: i32 = -9999;
+
+/// A single day of an element with its flags, blank flags are `None`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DlyDay {
+    pub day: u32,
+    /// the raw value in the element's units, e.g. tenths of a degree, `None` when missing
+    pub value: Option<i32>,
+    pub measurement_flag: Option<char>,
+    pub quality_flag: Option<char>,
+    pub source_flag: Option<char>,
+}
+
+/// One line of a `.dly` file, a month of a single element.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DlyLine {
+    pub station_id: String,
+    pub year: i32,
+    pub month: u32,
+    pub element: String,
+    pub days: Vec<DlyDay>,
+    /// values that could not be read, their days are left out of `days`
+    pub errors: Vec<ClimateError>,
+}
+
+/// The result of reading a file. Lines that could not be read are skipped and kept in `errors`.
+#[derive(Debug)]
+pub struct DlyReport {
+    pub records_added: usize,
+    /// values carrying a GHCN quality flag, added as `FlagKind::SourceRejected`
+    pub flags_added: usize,
+    pub errors: Vec<ClimateError>,
+}
+
+/// Parses a single line of a `.dly` file. Missing days keep their flags with a `None` value, a
+/// malformed value only drops its own day. A line without a valid year and month is an error.
+pub fn parse_dly_line(line: &str) -> Result<DlyLine, ClimateError> {
+    if !line.is_ascii() || line.len() < 29 {
+        return Err(ClimateError::parse(
+            "Line is not a GHCN-Daily record".to_string(),
+            None,
+            None,
+        ));
+    }
+    let field = |start: usize, end: usize| line.get(start..end.min(line.len())).unwrap_or("");
+    let number = |start: usize, end: usize, name: &str| {
+        field(start, end).trim().parse::<i32>().map_err(|_| {
+            ClimateError::parse(
+                format!("Invalid {} '{}'", name, field(start, end)),
+                None,
+                Some(&format!("{}-{}", start + 1, end)),
+            )
+        })
+    };
+    let flag = |index: usize| field(index, index + 1).chars().next().filter(|c| *c != ' ');
+
+    let year = number(11, 15, "year")?;
+    let month = number(15, 17, "month")?;
+    if !(1..=12).contains(&month) {
+        return Err(ClimateError::parse(
+            format!("Invalid month '{}'", month),
+            None,
+            Some("16-17"),
+        ));
+    }
+
+    let mut days = Vec::with_capacity(31);
+    let mut errors = Vec::new();
+    for day in 0..31 {
+        let start = 21 + day * 8;
+        if start >= line.len() {
+            break;
+        }
+        let value = match number(start, start + 5, "value") {
+            Ok(value) => value,
+            Err(e) => {
+                errors.push(e);
+                continue;
+            }
+        };
+        days.push(DlyDay {
+            day: day as u32 + 1,
+            value: (value != MISSING).then_some(value),
+            measurement_flag: flag(start + 5),
+            quality_flag: flag(start + 6),
+            source_flag: flag(start + 7),
+        });
+    }
+
+    Ok(DlyLine {
+        station_id: field(0, 11).trim().to_string(),
+        year,
+        month: month as u32,
+        element: field(17, 21).to_string(),
+        days,
+        errors,
+    })
+}
+
+/// Reads a `.dly` file and adds its daily records to `station`.
+pub fn read_dly_file(
+    path: impl AsRef<Path>,
+    station: &mut StationData,
+) -> Result<DlyReport, ClimateError> {
+    let path = path.as_ref();
+    let file = File::open(path)
+        .map_err(|e| ClimateError::Io(format!("Could not open {}: {}", path.display(), e)))?;
+    read_dly(file, station)
+}
+
+/// Reads the TMIN, TMAX, PRCP and AWND elements of a `.dly` file and adds a `DailyData` record
+/// to `station` for each day with both temperatures, in date order. Other elements are ignored.
+///
+/// Values NOAA's quality control failed are kept, with a `FlagKind::SourceRejected` flag naming
+/// the GHCN quality flag so the crate's own checks and refet runs can leave them out. Precipitation
+/// with an accumulation measurement flag gets a `FlagKind::Accumulation` flag and a trace a
+/// `FlagKind::Trace` flag. Lines and values that can't be parsed are reported in
+/// `DlyReport::errors` with their line number as the row.
+pub fn read_dly<R: Read>(reader: R, station: &mut StationData) -> Result<DlyReport, ClimateError> {
+    let mut days: BTreeMap<NaiveDate, Vec<(Variable, f64, DlyDay)>> = BTreeMap::new();
+    let mut report = DlyReport {
+        records_added: 0,
+        flags_added: 0,
+        errors: Vec::new(),
+    };
+
+    for (index, line) in BufReader::new(reader).lines().enumerate() {
+        let row = Some(index + 1);
+        let line = line.map_err(|e| ClimateError::Io(e.to_string()))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let dly_line = match parse_dly_line(&line) {
+            Ok(dly_line) => dly_line,
+            Err(ClimateError::Parse {
+                message, column, ..
+            }) => {
+                report.errors.push(ClimateError::Parse {
+                    message,
+                    row,
+                    column,
+                });
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+
+        for error in dly_line.errors.iter().cloned() {
+            if let ClimateError::Parse {
+                message, column, ..
+            } = error
+            {
+                report.errors.push(ClimateError::Parse {
+                    message,
+                    row,
+                    column,
+                });
+            }
+        }
+
+        let (variable, scale) = match dly_line.element.as_str() {
+            "TMIN" => (Variable::Tmin, 0.1),
+            "TMAX" => (Variable::Tmax, 0.1),
+            "PRCP" => (Variable::Precip, 0.1),
+            "AWND" => (Variable::WindSpeed, 0.1),
+            _ => continue,
+        };
+        for day in &dly_line.days {
+            let value = match day.value {
+                Some(value) => value,
+                None => continue,
+            };
+            match NaiveDate::from_ymd_opt(dly_line.year, dly_line.month, day.day) {
+                Some(date) => days.entry(date).or_default().push((
+                    variable,
+                    value as f64 * scale,
+                    day.clone(),
+                )),
+                None => report.errors.push(ClimateError::parse(
+                    format!(
+                        "Value for day {} of {}-{:02} which doesn't exist",
+                        day.day, dly_line.year, dly_line.month
+                    ),
+                    row,
+                    Some(&dly_line.element),
+                )),
+            }
+        }
+    }
+
+    for (date, values) in days {
+        let value = |variable: Variable| {
+            values
+                .iter()
+                .find(|(v, _, _)| *v == variable)
+                .map(|(_, value, _)| *value)
+        };
+        let (tmin, tmax) = match (value(Variable::Tmin), value(Variable::Tmax)) {
+            (Some(tmin), Some(tmax)) => (tmin, tmax),
+            _ => continue,
+        };
+
+        let mut daily_data = DailyData::new(
+            date,
+            (tmin, "°C".to_string()),
+            (tmax, "°C".to_string()),
+            None,
+            None,
+            None,
+            value(Variable::Precip).map(|precip| (precip, "mm".to_string())),
+            None,
+            None,
+            value(Variable::WindSpeed).map(|ws| (ws, "m/s".to_string())),
+        )?;
+        for (variable, _, day) in &values {
+            if let Some(code) = day.quality_flag {
+                let message = format!(
+                    "GHCN-Daily quality flag {}: {}",
+                    code,
+                    quality_flag_description(code)
+                );
+                report.flags_added += flag(
+                    &mut daily_data,
+                    *variable,
+                    FlagKind::SourceRejected,
+                    message,
+                );
+            }
+            let kind = match (variable, day.measurement_flag) {
+                (Variable::Precip, Some('A' | 'B')) => FlagKind::Accumulation,
+                (Variable::Precip, Some('T')) => FlagKind::Trace,
+                _ => continue,
+            };
+            let code = day.measurement_flag.unwrap_or_default();
+            let message = format!(
+                "GHCN-Daily measurement flag {}: {}",
+                code,
+                measurement_flag_description(code)
+            );
+            report.flags_added += flag(&mut daily_data, *variable, kind, message);
+        }
+        station.daily_data.push(daily_data);
+        report.records_added += 1;
+    }
+
+    Ok(report)
+}
+
+/// Builds a `StationData` from a line of `ghcnd-stations.txt`. The GHCN id is kept as the source
+/// since it doesn't fit the numeric station id, and the wind height is taken as 2 m.
+pub fn parse_station_line(line: &str) -> Result<StationData, ClimateError> {
+    if !line.is_ascii() || line.len() < 38 {
+        return Err(ClimateError::parse(
+            "Line is not a GHCN-Daily station".to_string(),
+            None,
+            None,
+        ));
+    }
+    let field = |start: usize, end: usize| line.get(start..end.min(line.len())).unwrap_or("");
+    let number = |start: usize, end: usize, name: &str| {
+        field(start, end).trim().parse::<f64>().map_err(|_| {
+            ClimateError::parse(
+                format!("Invalid {} '{}'", name, field(start, end)),
+                None,
+                Some(&format!("{}-{}", start + 1, end)),
+            )
+        })
+    };
+
+    Ok(StationData::new(
+        field(41, 71).trim().to_string(),
+        format!("GHCN-Daily {}", field(0, 11).trim()),
+        number(12, 20, "latitude")?,
+        number(21, 30, "longitude")?,
+        number(31, 37, "elevation")?,
+        2.0,
+        None,
+    ))
+}
+
+/// The meaning of a precipitation measurement flag that is carried into a `QcFlag`.
+pub fn measurement_flag_description(code: char) -> &'static str {
+    match code {
+        'A' => "total accumulated over more than one day",
+        'B' => "total formed from two 12-hour totals",
+        'T' => "trace of precipitation",
+        _ => "unknown measurement flag",
+    }
+}
+
+/// The meaning of a GHCN-Daily quality flag.
+pub fn quality_flag_description(code: char) -> &'static str {
+    match code {
+        'D' => "failed duplicate check",
+        'G' => "failed gap check",
+        'I' => "failed internal consistency check",
+        'K' => "failed streak/frequent-value check",
+        'L' => "failed check on length of multiday period",
+        'M' => "failed megaconsistency check",
+        'N' => "failed naught check",
+        'O' => "failed climatological outlier check",
+        'R' => "failed lagged range check",
+        'S' => "failed spatial consistency check",
+        'T' => "failed temporal consistency check",
+        'W' => "temperature too warm for snow",
+        'X' => "failed bounds check",
+        'Z' => "flagged as a result of an official Datzilla investigation",
+        _ => "unknown quality flag",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(name: &str) -> String {
+        format!("{}/testdata/ghcn/{}", env!("CARGO_MANIFEST_DIR"), name)
+    }
+
+    #[test]
+    fn parses_values_and_flags() {
+        let line = std::fs::read_to_string(sample("USC00251145.dly")).unwrap();
+        let dly_line = parse_dly_line(line.lines().next().unwrap()).unwrap();
+        assert_eq!(dly_line.station_id, "USC00251145");
+        assert_eq!((dly_line.year, dly_line.month), (2023, 7));
+        assert_eq!(dly_line.element, "TMAX");
+        assert_eq!(dly_line.days.len(), 31);
+        assert_eq!(
+            dly_line.days[0],
+            DlyDay {
+                day: 1,
+                value: Some(289),
+                measurement_flag: None,
+                quality_flag: None,
+                source_flag: Some('7'),
+            }
+        );
+        assert_eq!(dly_line.days[4].value, None);
+    }
+
+    #[test]
+    fn reads_station_file() {
+        let stations = std::fs::read_to_string(sample("ghcnd-stations.txt")).unwrap();
+        let mut station = parse_station_line(stations.lines().next().unwrap()).unwrap();
+        assert_eq!(station.name, "CLAY CENTER");
+        assert_eq!(station.source, "GHCN-Daily USC00251145");
+        assert_eq!(station.latitude, 40.5219);
+        assert_eq!(station.elevation, 531.0);
+
+        let report = read_dly_file(sample("USC00251145.dly"), &mut station).unwrap();
+        // day 5 is missing tmax, day 14 has a malformed precip and day 31 of June doesn't exist
+        assert_eq!(report.records_added, 30);
+        assert_eq!(report.errors.len(), 2);
+        assert_eq!(
+            report.errors[0].to_string(),
+            "Invalid value 'abcde' at row 3 in column 126-130"
+        );
+        assert!(report.errors[1].to_string().contains("day 31 of 2023-06"));
+
+        let first = &station.daily_data[0];
+        assert_eq!(first.date, NaiveDate::from_ymd_opt(2023, 7, 1).unwrap());
+        assert!((first.tmax - 28.9).abs() < 1e-9);
+        assert!((first.tmin - 15.6).abs() < 1e-9);
+        assert_eq!(first.precip, Some(0.0));
+        assert!((first.wind_speed.unwrap() - 3.1).abs() < 1e-9);
+
+        let malformed = &station.daily_data[12];
+        assert_eq!(
+            malformed.date,
+            NaiveDate::from_ymd_opt(2023, 7, 14).unwrap()
+        );
+        assert_eq!(malformed.precip, None);
+        assert!((malformed.tmax - 31.4).abs() < 1e-9);
+
+        assert_eq!(report.flags_added, 3);
+        let trace = station.daily_data[1].flags_for(Variable::Precip);
+        assert_eq!(trace[0].kind, FlagKind::Trace);
+        assert_eq!(station.daily_data[1].precip, Some(0.0));
+        let accumulated = &station.daily_data[18];
+        assert_eq!(
+            accumulated.date,
+            NaiveDate::from_ymd_opt(2023, 7, 20).unwrap()
+        );
+        let flags = accumulated.flags_for(Variable::Precip);
+        assert_eq!(flags[0].kind, FlagKind::Accumulation);
+        assert_eq!(
+            flags[0].message,
+            "GHCN-Daily measurement flag A: total accumulated over more than one day"
+        );
+
+        let flagged = &station.daily_data[9];
+        assert_eq!(flagged.date, NaiveDate::from_ymd_opt(2023, 7, 11).unwrap());
+        let flags = flagged.flags_for(Variable::Tmax);
+        assert_eq!(flags[0].kind, FlagKind::SourceRejected);
+        assert_eq!(
+            flags[0].message,
+            "GHCN-Daily quality flag O: failed climatological outlier check"
+        );
+    }
+}
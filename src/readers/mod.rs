@@ -0,0 +1,6 @@
+//! Readers that build `StationData` from climate data files.
+
+pub mod csv;
+pub mod ghcn;
+pub mod netcdf;
+pub mod toa5;
@@ -0,0 +1,633 @@
+//! Extracts station-like daily series from gridded NetCDF products such as gridMET, Daymet and
+//! PRISM.
+//!
+//! A `Grid` is built from the coordinate variables of one file and then takes each climate
+//! variable, and the elevation from a DEM, from the same or other files, since products like
+//! gridMET publish one variable per file. Every cell becomes its own `StationData`. Variables
+//! must be laid out as `(time, rows, columns)`, with latitude and longitude either one
+//! dimensional axes or two dimensional `(rows, columns)` arrays as in Daymet's projected grids.
+//!
+//! Files are read through the `GridSource` trait. The optional `netcdf` feature implements it
+//! for the NetCDF-3 classic and 64-bit offset formats only, in pure Rust. gridMET and Daymet ship
+//! NetCDF-4 (HDF5) files, which `open_netcdf` rejects; convert them with `nccopy -k classic` or
+//! read them through another `GridSource` implementation.
+
+use crate::data_model::{convert_to_output_units, DailyData, StationData, Variable};
+use crate::error::ClimateError;
+use crate::units::Units;
+use chrono::{Duration, NaiveDate};
+
+/// A variable read from a grid file, unpacked with its `scale_factor` and `add_offset` and with
+/// fill and missing values as `None`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GridVariable {
+    /// dimension names and sizes, slowest varying first
+    pub dimensions: Vec<(String, usize)>,
+    pub values: Vec<Option<f64>>,
+    /// the `units` attribute
+    pub units: Option<String>,
+}
+
+/// Anything that can read whole variables from a gridded file.
+pub trait GridSource {
+    fn read_variable(&mut self, name: &str) -> Result<GridVariable, ClimateError>;
+}
+
+/// Opens a NetCDF-3 file for use as a `GridSource`. NetCDF-4 files are an error naming the
+/// format, since only NetCDF-3 can be read.
+#[cfg(feature = "netcdf")]
+pub fn open_netcdf(path: impl AsRef<std::path::Path>) -> Result<netcdf3::FileReader, ClimateError> {
+    use std::io::Read;
+
+    let path = path.as_ref();
+    let mut signature = [0u8; 8];
+    let is_hdf5 = std::fs::File::open(path)
+        .and_then(|mut file| file.read_exact(&mut signature))
+        .is_ok()
+        && signature == *b"\x89HDF\r\n\x1a\n";
+    if is_hdf5 {
+        return Err(ClimateError::Io(format!(
+            "Could not open {}: it is a NetCDF-4 (HDF5) file and only NetCDF-3 can be read, \
+             convert it with `nccopy -k classic`",
+            path.display()
+        )));
+    }
+    netcdf3::FileReader::open(path)
+        .map_err(|e| ClimateError::Io(format!("Could not open {}: {}", path.display(), e)))
+}
+
+#[cfg(feature = "netcdf")]
+impl GridSource for netcdf3::FileReader {
+    fn read_variable(&mut self, name: &str) -> Result<GridVariable, ClimateError> {
+        use netcdf3::DataVector;
+
+        let data_set = self.data_set();
+        let variable = data_set.get_var(name).ok_or_else(|| {
+            ClimateError::parse(format!("Variable {} not found", name), None, Some(name))
+        })?;
+        let dimensions = variable
+            .get_dims()
+            .iter()
+            .map(|dim| (dim.name(), dim.size()))
+            .collect();
+        let attribute = |attribute: &str| {
+            let attribute = variable.get_attr(attribute)?;
+            match attribute.data_type() {
+                netcdf3::DataType::U8 => attribute.get_u8().map(|v| v[0] as f64),
+                netcdf3::DataType::I8 => attribute.get_i8().map(|v| v[0] as f64),
+                netcdf3::DataType::I16 => attribute.get_i16().map(|v| v[0] as f64),
+                netcdf3::DataType::I32 => attribute.get_i32().map(|v| v[0] as f64),
+                netcdf3::DataType::F32 => attribute.get_f32().map(|v| v[0] as f64),
+                netcdf3::DataType::F64 => attribute.get_f64().map(|v| v[0]),
+            }
+        };
+        let scale = attribute("scale_factor").unwrap_or(1.0);
+        let offset = attribute("add_offset").unwrap_or(0.0);
+        let fill_values: Vec<f64> = [attribute("_FillValue"), attribute("missing_value")]
+            .into_iter()
+            .flatten()
+            .collect();
+        let units = variable.get_attr_as_string("units");
+
+        let raw: Vec<f64> = match self
+            .read_var(name)
+            .map_err(|e| ClimateError::Io(format!("Could not read {}: {}", name, e)))?
+        {
+            DataVector::I8(values) => values.into_iter().map(f64::from).collect(),
+            DataVector::U8(values) => values.into_iter().map(f64::from).collect(),
+            DataVector::I16(values) => values.into_iter().map(f64::from).collect(),
+            DataVector::I32(values) => values.into_iter().map(f64::from).collect(),
+            DataVector::F32(values) => values.into_iter().map(f64::from).collect(),
+            DataVector::F64(values) => values,
+        };
+        let values = raw
+            .into_iter()
+            .map(|value| {
+                let missing = value.is_nan()
+                    || fill_values.contains(&value)
+                    || value == netcdf3::NC_FILL_F32 as f64
+                    || value == netcdf3::NC_FILL_F64;
+                (!missing).then_some(value * scale + offset)
+            })
+            .collect();
+
+        Ok(GridVariable {
+            dimensions,
+            values,
+            units,
+        })
+    }
+}
+
+/// Names the coordinate variables of a grid and describes the product.
+#[derive(Debug, Clone)]
+pub struct GridCoordinates {
+    /// written as the `source` of each `StationData`
+    pub source: String,
+    /// the time variable, in days since the date in its `units`
+    pub time: String,
+    pub latitude: String,
+    pub longitude: String,
+    /// height of the product's wind speed in meters
+    pub wind_height: f64,
+}
+
+impl GridCoordinates {
+    /// gridMET, with wind speed at 10 m.
+    pub fn gridmet() -> GridCoordinates {
+        GridCoordinates {
+            source: "gridMET".to_string(),
+            time: "day".to_string(),
+            latitude: "lat".to_string(),
+            longitude: "lon".to_string(),
+            wind_height: 10.0,
+        }
+    }
+
+    /// Daymet, which has no wind speed.
+    pub fn daymet() -> GridCoordinates {
+        GridCoordinates {
+            source: "Daymet".to_string(),
+            time: "time".to_string(),
+            latitude: "lat".to_string(),
+            longitude: "lon".to_string(),
+            wind_height: 2.0,
+        }
+    }
+}
+
+/// Daily grids held in memory, ready to be extracted as `StationData`.
+#[derive(Debug, Clone)]
+pub struct Grid {
+    source: String,
+    wind_height: f64,
+    rows: usize,
+    columns: usize,
+    dates: Vec<NaiveDate>,
+    /// latitude and longitude of each cell, row major
+    cells: Vec<(f64, f64)>,
+    elevation: Option<Vec<Option<f64>>>,
+    /// daylight seconds, used to turn a daylight mean flux into a daily total
+    day_length: Option<Vec<Option<f64>>>,
+    /// each variable in SI units with its values for `(time, row, column)`
+    variables: Vec<(Variable, String, Vec<Option<f64>>)>,
+}
+
+impl Grid {
+    /// Reads the time, latitude and longitude variables. The grid takes its rows and columns
+    /// from the latitude and longitude, the size of each axis or of the two dimensional arrays.
+    pub fn read(
+        source: &mut impl GridSource,
+        coordinates: &GridCoordinates,
+    ) -> Result<Grid, ClimateError> {
+        let time = source.read_variable(&coordinates.time)?;
+        let epoch = time.units.as_deref().and_then(days_since).ok_or_else(|| {
+            ClimateError::parse(
+                format!(
+                    "Time units {:?} are not days since a date",
+                    time.units.unwrap_or_default()
+                ),
+                None,
+                Some(&coordinates.time),
+            )
+        })?;
+        let dates = time
+            .values
+            .iter()
+            .map(|day| {
+                day.map(|day| epoch + Duration::days(day.floor() as i64))
+                    .ok_or_else(|| {
+                        ClimateError::parse(
+                            "Missing time value".to_string(),
+                            None,
+                            Some(&coordinates.time),
+                        )
+                    })
+            })
+            .collect::<Result<Vec<NaiveDate>, ClimateError>>()?;
+
+        let latitude = source.read_variable(&coordinates.latitude)?;
+        let longitude = source.read_variable(&coordinates.longitude)?;
+        let coordinate = |value: Option<f64>, name: &str| {
+            value.ok_or_else(|| {
+                ClimateError::parse("Missing coordinate".to_string(), None, Some(name))
+            })
+        };
+        let (rows, columns, cells) = match (
+            latitude.dimensions.as_slice(),
+            longitude.dimensions.as_slice(),
+        ) {
+            ([(_, rows)], [(_, columns)]) => {
+                let mut cells = Vec::with_capacity(rows * columns);
+                for lat in &latitude.values {
+                    for long in &longitude.values {
+                        cells.push((
+                            coordinate(*lat, &coordinates.latitude)?,
+                            coordinate(*long, &coordinates.longitude)?,
+                        ));
+                    }
+                }
+                (*rows, *columns, cells)
+            }
+            ([(_, rows), (_, columns)], [_, _]) if latitude.dimensions == longitude.dimensions => {
+                let cells = latitude
+                    .values
+                    .iter()
+                    .zip(&longitude.values)
+                    .map(|(lat, long)| {
+                        Ok((
+                            coordinate(*lat, &coordinates.latitude)?,
+                            coordinate(*long, &coordinates.longitude)?,
+                        ))
+                    })
+                    .collect::<Result<Vec<(f64, f64)>, ClimateError>>()?;
+                (*rows, *columns, cells)
+            }
+            _ => {
+                return Err(ClimateError::parse(
+                    "Latitude and longitude must be axes or arrays with the same dimensions"
+                        .to_string(),
+                    None,
+                    Some(&coordinates.latitude),
+                ))
+            }
+        };
+
+        Ok(Grid {
+            source: coordinates.source.clone(),
+            wind_height: coordinates.wind_height,
+            rows,
+            columns,
+            dates,
+            cells,
+            elevation: None,
+            day_length: None,
+            variables: Vec::new(),
+        })
+    }
+
+    /// Adds a daily variable, converted to SI units from its `units` attribute. Radiation as a
+    /// flux in W/m² is taken as the daily mean, or as the daylight mean when a day length has
+    /// been added, and stored in MJ/m²/day.
+    pub fn add_variable(
+        &mut self,
+        source: &mut impl GridSource,
+        variable: Variable,
+        name: &str,
+    ) -> Result<(), ClimateError> {
+        let grid_variable = source.read_variable(name)?;
+        self.check_shape(&grid_variable, true, name)?;
+        let units = grid_variable.units.as_deref().unwrap_or("");
+        let output_units = variable.output_units().to_abbreviation().to_string();
+
+        let values = match (variable, Units::from_abbreviation(units)) {
+            (Variable::Rs, Ok(Units::WattsPerSquareMeter)) => {
+                let mut values = grid_variable.values;
+                for (i, value) in values.iter_mut().enumerate() {
+                    // a daylight mean flux is scaled to the mean over the whole day
+                    let flux = match &self.day_length {
+                        Some(day_length) => value
+                            .zip(day_length[i])
+                            .map(|(flux, seconds)| flux * seconds / 86400.0),
+                        None => *value,
+                    };
+                    *value = flux
+                        .map(|flux| {
+                            Units::WattsPerSquareMeter
+                                .convert(flux, &Units::MegaJoulesPerSquareMeterPerDay)
+                        })
+                        .transpose()?;
+                }
+                values
+            }
+            (_, units) => {
+                let units = units.map_err(|e| e.with_variable(variable.name()))?;
+                grid_variable
+                    .values
+                    .iter()
+                    .map(|value| {
+                        value
+                            .map(|v| convert_to_output_units(variable, v, units.to_abbreviation()))
+                            .transpose()
+                    })
+                    .collect::<Result<Vec<Option<f64>>, ClimateError>>()
+                    .map_err(|e| e.with_variable(variable.name()))?
+            }
+        };
+
+        self.variables.retain(|(v, _, _)| *v != variable);
+        self.variables.push((variable, output_units, values));
+        Ok(())
+    }
+
+    /// Adds the elevation of each cell in meters from a DEM variable on the same grid.
+    pub fn add_elevation(
+        &mut self,
+        source: &mut impl GridSource,
+        name: &str,
+    ) -> Result<(), ClimateError> {
+        let elevation = source.read_variable(name)?;
+        self.check_shape(&elevation, false, name)?;
+        let units = elevation.units.as_deref().unwrap_or("m");
+        let values = match Units::from_abbreviation(units)? {
+            Units::Meters => elevation.values,
+            from => elevation
+                .values
+                .iter()
+                .map(|value| value.map(|v| from.convert(v, &Units::Meters)).transpose())
+                .collect::<Result<Vec<Option<f64>>, ClimateError>>()?,
+        };
+        self.elevation = Some(values);
+        Ok(())
+    }
+
+    /// Adds the daylight seconds of each day, such as Daymet's `dayl`, which must be added before
+    /// a radiation variable that is a daylight mean flux.
+    pub fn add_day_length(
+        &mut self,
+        source: &mut impl GridSource,
+        name: &str,
+    ) -> Result<(), ClimateError> {
+        let day_length = source.read_variable(name)?;
+        self.check_shape(&day_length, true, name)?;
+        self.day_length = Some(day_length.values);
+        Ok(())
+    }
+
+    /// The series for the cell nearest to a point given in degrees.
+    pub fn point(&self, latitude: f64, longitude: f64) -> Result<StationData, ClimateError> {
+        let distance = |(lat, long): (f64, f64)| {
+            let d_long = (long - longitude) * latitude.to_radians().cos();
+            (lat - latitude).powi(2) + d_long.powi(2)
+        };
+        let cell = (0..self.cells.len())
+            .min_by(|a, b| distance(self.cells[*a]).total_cmp(&distance(self.cells[*b])))
+            .ok_or_else(|| ClimateError::invalid_value("The grid has no cells".to_string()))?;
+        self.station(cell)
+    }
+
+    /// The series for every cell inside a bounding box given in degrees, skipping cells with no
+    /// elevation or no days of tmin and tmax such as those over water.
+    pub fn bounding_box(
+        &self,
+        min_latitude: f64,
+        max_latitude: f64,
+        min_longitude: f64,
+        max_longitude: f64,
+    ) -> Result<Vec<StationData>, ClimateError> {
+        let mut stations = Vec::new();
+        for (cell, (lat, long)) in self.cells.iter().enumerate() {
+            let no_elevation = self
+                .elevation
+                .as_ref()
+                .is_some_and(|elevation| elevation[cell].is_none());
+            if (min_latitude..=max_latitude).contains(lat)
+                && (min_longitude..=max_longitude).contains(long)
+                && !no_elevation
+            {
+                let station = self.station(cell)?;
+                if !station.daily_data.is_empty() {
+                    stations.push(station);
+                }
+            }
+        }
+        Ok(stations)
+    }
+
+    /// Builds the `StationData` for a cell, with a record for each day that has tmin and tmax.
+    fn station(&self, cell: usize) -> Result<StationData, ClimateError> {
+        let elevation = self
+            .elevation
+            .as_ref()
+            .ok_or_else(|| {
+                ClimateError::invalid_value(
+                    "Add the elevation before extracting stations".to_string(),
+                )
+            })?
+            .get(cell)
+            .copied()
+            .flatten()
+            .ok_or_else(|| ClimateError::invalid_value("The cell has no elevation".to_string()))?;
+        let (latitude, longitude) = self.cells[cell];
+        let mut station = StationData::new(
+            format!("{:.4}, {:.4}", latitude, longitude),
+            self.source.clone(),
+            latitude,
+            longitude,
+            elevation,
+            self.wind_height,
+            None,
+        );
+
+        let cells = self.rows * self.columns;
+        for (day, date) in self.dates.iter().enumerate() {
+            let mut values: [Option<(f64, String)>; 10] = Default::default();
+            for (variable, units, grid) in &self.variables {
+                let position = Variable::all().iter().position(|v| v == variable).unwrap();
+                values[position] = grid[day * cells + cell].map(|v| (v, units.to_string()));
+            }
+            let [tmin, tmax, rhmin, rhmax, dewpoint, precip, rs, ea, wind_speed, pressure] = values;
+            if let (Some(tmin), Some(tmax)) = (tmin, tmax) {
+                let mut daily_data = DailyData::new(
+                    *date, tmin, tmax, rhmin, rhmax, dewpoint, precip, rs, ea, wind_speed,
+                )?;
+                if let Some(pressure) = pressure {
+                    daily_data = daily_data.with_pressure(pressure)?;
+                }
+                station.daily_data.push(daily_data);
+            }
+        }
+        Ok(station)
+    }
+
+    /// Checks a variable covers the grid, and every day when `daily` is set.
+    fn check_shape(
+        &self,
+        variable: &GridVariable,
+        daily: bool,
+        name: &str,
+    ) -> Result<(), ClimateError> {
+        let sizes: Vec<usize> = variable.dimensions.iter().map(|(_, size)| *size).collect();
+        let expected = if daily {
+            vec![self.dates.len(), self.rows, self.columns]
+        } else {
+            vec![self.rows, self.columns]
+        };
+        if sizes != expected {
+            return Err(ClimateError::parse(
+                format!("Dimensions {:?} don't match the grid {:?}", sizes, expected),
+                None,
+                Some(name),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// The epoch of CF time units such as `days since 1900-01-01 00:00:00`.
+fn days_since(units: &str) -> Option<NaiveDate> {
+    let date = units.trim().strip_prefix("days since ")?;
+    NaiveDate::parse_from_str(date.split_whitespace().next()?, "%Y-%m-%d").ok()
+}
+
+#[cfg(all(test, feature = "netcdf"))]
+mod tests {
+    use super::*;
+    use netcdf3::{DataSet, DataType, FileWriter, Version};
+    use std::path::PathBuf;
+
+    /// Writes a gridMET style file with a 2 x 3 grid over 3 days, the top left cell is water.
+    fn fixture(name: &str, variable: &str, units: &str, values: &[i32]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("climate_{}_{}", std::process::id(), name));
+        let mut data_set = DataSet::new();
+        data_set.set_unlimited_dim("day", 3).unwrap();
+        data_set.add_fixed_dim("lat", 2).unwrap();
+        data_set.add_fixed_dim("lon", 3).unwrap();
+        data_set.add_var_f64("day", &["day"]).unwrap();
+        data_set
+            .add_var_attr_string("day", "units", "days since 1900-01-01 00:00:00")
+            .unwrap();
+        data_set.add_var_f64("lat", &["lat"]).unwrap();
+        data_set.add_var_f64("lon", &["lon"]).unwrap();
+        data_set
+            .add_var(variable, &["day", "lat", "lon"], DataType::I32)
+            .unwrap();
+        data_set
+            .add_var_attr_string(variable, "units", units)
+            .unwrap();
+        data_set
+            .add_var_attr_f64(variable, "scale_factor", vec![0.1])
+            .unwrap();
+        data_set
+            .add_var_attr_i32(variable, "_FillValue", vec![-32767])
+            .unwrap();
+        data_set.add_var_i32("elevation", &["lat", "lon"]).unwrap();
+        data_set
+            .add_var_attr_string("elevation", "units", "m")
+            .unwrap();
+        data_set
+            .add_var_attr_i32("elevation", "_FillValue", vec![-32767])
+            .unwrap();
+
+        let mut writer = FileWriter::create_new(&path).unwrap();
+        writer.set_def(&data_set, Version::Classic, 0).unwrap();
+        writer
+            .write_var_f64("day", &[45106.0, 45107.0, 45108.0])
+            .unwrap();
+        writer.write_var_f64("lat", &[41.0, 40.9583]).unwrap();
+        writer
+            .write_var_f64("lon", &[-98.0417, -98.0, -97.9583])
+            .unwrap();
+        writer.write_var_i32(variable, values).unwrap();
+        writer
+            .write_var_i32("elevation", &[-32767, 540, 545, 530, 531, 532])
+            .unwrap();
+        writer.close().unwrap();
+        path
+    }
+
+    fn days(cell_values: [i32; 6]) -> Vec<i32> {
+        let mut values = Vec::new();
+        for day in 0..3 {
+            values.extend(
+                cell_values
+                    .iter()
+                    .map(|v| if *v == -32767 { *v } else { v + day * 10 }),
+            );
+        }
+        values
+    }
+
+    #[test]
+    fn extracts_points_and_boxes() {
+        let tmin = fixture(
+            "tmmn.nc",
+            "air_temperature",
+            "K",
+            &days([-32767, 2880, 2881, 2882, 2883, 2884]),
+        );
+        let tmax = fixture(
+            "tmmx.nc",
+            "air_temperature",
+            "K",
+            &days([-32767, 3030, 3031, 3032, 3033, 3034]),
+        );
+        let srad = fixture(
+            "srad.nc",
+            "surface_downwelling_shortwave_flux_in_air",
+            "W m-2",
+            &days([-32767, 3000, 3000, 3000, 3000, 3000]),
+        );
+        let pr = fixture(
+            "pr.nc",
+            "precipitation_amount",
+            "kg m-2",
+            &days([-32767, 25, 25, 25, 25, 25]),
+        );
+
+        let mut tmin_file = open_netcdf(&tmin).unwrap();
+        let mut grid = Grid::read(&mut tmin_file, &GridCoordinates::gridmet()).unwrap();
+        grid.add_variable(&mut tmin_file, Variable::Tmin, "air_temperature")
+            .unwrap();
+        grid.add_elevation(&mut tmin_file, "elevation").unwrap();
+        grid.add_variable(
+            &mut open_netcdf(&tmax).unwrap(),
+            Variable::Tmax,
+            "air_temperature",
+        )
+        .unwrap();
+        grid.add_variable(
+            &mut open_netcdf(&srad).unwrap(),
+            Variable::Rs,
+            "surface_downwelling_shortwave_flux_in_air",
+        )
+        .unwrap();
+        grid.add_variable(
+            &mut open_netcdf(&pr).unwrap(),
+            Variable::Precip,
+            "precipitation_amount",
+        )
+        .unwrap();
+
+        let station = grid.point(40.96, -97.99).unwrap();
+        assert_eq!(station.name, "40.9583, -98.0000");
+        assert_eq!(station.source, "gridMET");
+        assert_eq!(station.elevation, 531.0);
+        assert_eq!(station.wind_height, 10.0);
+        assert_eq!(station.daily_data.len(), 3);
+        let first = &station.daily_data[0];
+        assert_eq!(first.date, NaiveDate::from_ymd_opt(2023, 7, 1).unwrap());
+        assert!((first.tmin - 15.15).abs() < 1e-9);
+        assert!((first.tmax - 30.15).abs() < 1e-9);
+        assert_eq!(first.tmin_units, "°C");
+        assert!((first.rs.unwrap() - 300.0 * 0.0864).abs() < 1e-9);
+        assert!((first.precip.unwrap() - 2.5).abs() < 1e-9);
+        assert_eq!(first.precip_units.as_deref(), Some("mm"));
+        assert_eq!(
+            station.daily_data[2].date,
+            NaiveDate::from_ymd_opt(2023, 7, 3).unwrap()
+        );
+
+        // the water cell in the top left has no data or elevation and is skipped
+        let stations = grid.bounding_box(40.9, 41.1, -98.1, -97.99).unwrap();
+        assert_eq!(stations.len(), 3);
+
+        let error = grid
+            .add_variable(&mut tmin_file, Variable::WindSpeed, "air_temperature")
+            .unwrap_err();
+        assert_eq!(error.variable(), Some("wind_speed"));
+
+        for path in [tmin, tmax, srad, pr] {
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn rejects_netcdf4() {
+        let path = std::env::temp_dir().join(format!("climate_{}_hdf5.nc", std::process::id()));
+        std::fs::write(&path, b"\x89HDF\r\n\x1a\n\0\0\0\0").unwrap();
+        let error = open_netcdf(&path).unwrap_err().to_string();
+        std::fs::remove_file(&path).unwrap();
+        assert!(error.contains("NetCDF-4"), "{}", error);
+    }
+}
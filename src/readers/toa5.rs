@@ -0,0 +1,567 @@
+//! Reads Campbell Scientific TOA5 datalogger files and aggregates the sub-daily records to
+//! `DailyData`.
+//!
+//! A TOA5 file starts with four header lines: the file environment (format, station name, logger
+//! model, serial number, OS version, program, signature and table name), the field names, the
+//! units of each field and how the logger processed each field (`Avg`, `Max`, `Tot`, ...).
+//! Timestamps mark the end of the logging interval, so the `00:00` record closes the day before.
+
+use crate::data_model::{convert_to_output_units, DailyData, StationData, Variable};
+use crate::error::ClimateError;
+use crate::quantity::{RadiantExposure, RadiantExposureUnits};
+use crate::units::{Dimension, Period, Units};
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+use csv::{ReaderBuilder, StringRecord};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// The four header lines of a TOA5 file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Toa5Header {
+    pub station_name: String,
+    pub logger_model: String,
+    pub serial_number: String,
+    pub os_version: String,
+    pub program: String,
+    pub program_signature: String,
+    pub table_name: String,
+    pub fields: Vec<String>,
+    pub units: Vec<String>,
+    pub processing: Vec<String>,
+}
+
+impl Toa5Header {
+    /// The index and `Units` of a field, from the units row.
+    fn field_units(&self, field: &str) -> Result<(usize, Units), ClimateError> {
+        let index = self.fields.iter().position(|f| f == field).ok_or_else(|| {
+            ClimateError::parse(
+                "Field not found in header".to_string(),
+                Some(2),
+                Some(field),
+            )
+        })?;
+        let units = self.units.get(index).map(String::as_str).unwrap_or("");
+        Ok((index, Units::from_abbreviation(units)?))
+    }
+}
+
+/// Names the TOA5 fields holding each variable. Units are read from the file's units row.
+///
+/// The daily tmin is the lowest value of the `tmin` field and the daily tmax the highest value of
+/// the `tmax` field, so both can name the same field when the table only has an average or
+/// sample; rhmin and rhmax work the same way. Wind speed is averaged, precipitation summed and
+/// radiation integrated over the day.
+#[derive(Debug, Clone)]
+pub struct Toa5Mapping {
+    pub timestamp_field: String,
+    pub tmin: String,
+    pub tmax: String,
+    pub rhmin: Option<String>,
+    pub rhmax: Option<String>,
+    pub rs: Option<String>,
+    pub precip: Option<String>,
+    pub wind_speed: Option<String>,
+    pub pressure: Option<String>,
+    /// the logging interval, found from the smallest step between timestamps when `None`
+    pub interval: Option<Duration>,
+    /// fraction of the day's records a variable needs for a daily value, between 0 and 1
+    pub min_completeness: f64,
+}
+
+impl Default for Toa5Mapping {
+    fn default() -> Self {
+        Toa5Mapping {
+            timestamp_field: "TIMESTAMP".to_string(),
+            tmin: "AirTC_Min".to_string(),
+            tmax: "AirTC_Max".to_string(),
+            rhmin: Some("RH_Min".to_string()),
+            rhmax: Some("RH_Max".to_string()),
+            rs: Some("SlrW_Avg".to_string()),
+            precip: Some("Rain_mm_Tot".to_string()),
+            wind_speed: Some("WS_ms_Avg".to_string()),
+            pressure: None,
+            interval: None,
+            min_completeness: 0.9,
+        }
+    }
+}
+
+/// The result of reading a file. Records that could not be read, repeated timestamps and days
+/// that could not form a `DailyData` are skipped and kept in `errors`, days without enough tmin
+/// and tmax records are skipped and kept in `incomplete_days`.
+#[derive(Debug)]
+pub struct Toa5Report {
+    pub header: Toa5Header,
+    pub records_added: usize,
+    pub incomplete_days: Vec<NaiveDate>,
+    pub errors: Vec<ClimateError>,
+}
+
+/// How a variable's sub-daily values become a daily value.
+#[derive(Debug, Clone, Copy)]
+enum Aggregation {
+    Min,
+    Max,
+    Mean,
+    Sum,
+}
+
+/// A mapped field and where its values are collected.
+struct Field {
+    variable: Variable,
+    index: usize,
+    units: Units,
+    aggregation: Aggregation,
+}
+
+/// Reads a TOA5 file and adds its daily records to `station`.
+pub fn read_toa5_file(
+    path: impl AsRef<Path>,
+    station: &mut StationData,
+    mapping: &Toa5Mapping,
+) -> Result<Toa5Report, ClimateError> {
+    let path = path.as_ref();
+    let file = File::open(path)
+        .map_err(|e| ClimateError::Io(format!("Could not open {}: {}", path.display(), e)))?;
+    read_toa5(file, station, mapping)
+}
+
+/// Reads TOA5 data, aggregates it to daily values and adds a `DailyData` record to `station` for
+/// each day with complete tmin and tmax, in date order.
+///
+/// A variable needs `min_completeness` of the records expected for the day, or the optional
+/// variables are left empty. Radiation logged as W/m² is taken as the mean flux of each interval
+/// and integrated to MJ/m²/day, MJ/m² totals are integrated the same way after dividing by the
+/// interval, so missing intervals are filled with the day's mean rather than counted as zero.
+/// Precipitation is summed as logged. A timestamp logged more than once, as after a re-download,
+/// keeps the first record in the file. Unknown units, units that don't fit the variable and
+/// missing fields are returned as an error, bad records are reported in `Toa5Report::errors`.
+pub fn read_toa5<R: Read>(
+    reader: R,
+    station: &mut StationData,
+    mapping: &Toa5Mapping,
+) -> Result<Toa5Report, ClimateError> {
+    let mut csv_reader = ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(reader);
+    let mut records = csv_reader.records();
+    let mut header_line = |row: usize| {
+        records
+            .next()
+            .unwrap_or_else(|| Err(csv::Error::from(std::io::Error::other("file ends early"))))
+            .map_err(|e| {
+                ClimateError::parse(format!("Invalid TOA5 header: {}", e), Some(row), None)
+            })
+    };
+    let environment = header_line(1)?;
+    if environment.get(0) != Some("TOA5") {
+        return Err(ClimateError::parse(
+            "Not a TOA5 file".to_string(),
+            Some(1),
+            None,
+        ));
+    }
+    let strings = |record: StringRecord| record.iter().map(|s| s.to_string()).collect();
+    let environment_field = |index: usize| environment.get(index).unwrap_or("").to_string();
+    let header = Toa5Header {
+        station_name: environment_field(1),
+        logger_model: environment_field(2),
+        serial_number: environment_field(3),
+        os_version: environment_field(4),
+        program: environment_field(5),
+        program_signature: environment_field(6),
+        table_name: environment_field(7),
+        fields: strings(header_line(2)?),
+        units: strings(header_line(3)?),
+        processing: strings(header_line(4)?),
+    };
+
+    let timestamp_index = header
+        .fields
+        .iter()
+        .position(|f| *f == mapping.timestamp_field)
+        .ok_or_else(|| {
+            ClimateError::parse(
+                "Field not found in header".to_string(),
+                Some(2),
+                Some(&mapping.timestamp_field),
+            )
+        })?;
+    let mut fields = Vec::new();
+    for (variable, field, aggregation) in [
+        (Variable::Tmin, Some(&mapping.tmin), Aggregation::Min),
+        (Variable::Tmax, Some(&mapping.tmax), Aggregation::Max),
+        (Variable::RhMin, mapping.rhmin.as_ref(), Aggregation::Min),
+        (Variable::RhMax, mapping.rhmax.as_ref(), Aggregation::Max),
+        (Variable::Rs, mapping.rs.as_ref(), Aggregation::Mean),
+        (Variable::Precip, mapping.precip.as_ref(), Aggregation::Sum),
+        (
+            Variable::WindSpeed,
+            mapping.wind_speed.as_ref(),
+            Aggregation::Mean,
+        ),
+        (
+            Variable::Pressure,
+            mapping.pressure.as_ref(),
+            Aggregation::Mean,
+        ),
+    ] {
+        let field = match field {
+            Some(field) => field,
+            None => continue,
+        };
+        let (index, units) = header
+            .field_units(field)
+            .map_err(|e| e.with_variable(variable.name()))?;
+        if convert_to_output_units(variable, 0.0, units.to_abbreviation()).is_err() {
+            return Err(ClimateError::unsupported_conversion(
+                units.name(),
+                variable.output_units().name(),
+            )
+            .with_variable(variable.name()));
+        }
+        fields.push(Field {
+            variable,
+            index,
+            units,
+            aggregation,
+        });
+    }
+
+    let mut report = Toa5Report {
+        header,
+        records_added: 0,
+        incomplete_days: Vec::new(),
+        errors: Vec::new(),
+    };
+    let mut rows: Vec<(NaiveDateTime, Option<usize>, Vec<Option<f64>>)> = Vec::new();
+    for record in records {
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                let row = e.position().map(|p| p.line() as usize);
+                report
+                    .errors
+                    .push(ClimateError::parse(e.to_string(), row, None));
+                continue;
+            }
+        };
+        let row = record.position().map(|p| p.line() as usize);
+        let timestamp_cell = record.get(timestamp_index).unwrap_or("");
+        let timestamp = match parse_timestamp(timestamp_cell) {
+            Some(timestamp) => timestamp,
+            None => {
+                report.errors.push(ClimateError::parse(
+                    format!("Invalid timestamp '{}'", timestamp_cell),
+                    row,
+                    Some(&mapping.timestamp_field),
+                ));
+                continue;
+            }
+        };
+
+        let mut values = Vec::with_capacity(fields.len());
+        for field in &fields {
+            let cell = record.get(field.index).unwrap_or("").trim();
+            values.push(match cell {
+                "" | "NAN" | "NaN" | "INF" | "-INF" => None,
+                cell => match cell.parse::<f64>() {
+                    Ok(value) => Some(value),
+                    Err(_) => {
+                        report.errors.push(ClimateError::parse(
+                            format!("Invalid number '{}'", cell),
+                            row,
+                            Some(&report.header.fields[field.index]),
+                        ));
+                        None
+                    }
+                },
+            });
+        }
+        rows.push((timestamp, row, values));
+    }
+
+    // a timestamp repeated by a re-download keeps its first record, the rest are reported
+    rows.sort_by_key(|(timestamp, _, _)| *timestamp);
+    let mut unique_rows: Vec<(NaiveDateTime, Option<usize>, Vec<Option<f64>>)> =
+        Vec::with_capacity(rows.len());
+    for (timestamp, row, values) in rows {
+        match unique_rows.last() {
+            Some((kept, kept_row, _)) if *kept == timestamp => {
+                let kept_at = kept_row.map_or(String::new(), |r| format!(" at row {}", r));
+                report.errors.push(ClimateError::parse(
+                    format!(
+                        "Timestamp '{}' repeats the record{}, which is kept",
+                        timestamp, kept_at
+                    ),
+                    row,
+                    Some(&mapping.timestamp_field),
+                ));
+            }
+            _ => unique_rows.push((timestamp, row, values)),
+        }
+    }
+    let rows = unique_rows;
+
+    let interval = match mapping.interval {
+        Some(interval) => interval,
+        None => match rows.windows(2).map(|pair| pair[1].0 - pair[0].0).min() {
+            Some(interval) => interval,
+            None if rows.is_empty() => return Ok(report),
+            None => {
+                return Err(ClimateError::invalid_value(
+                    "The logging interval can't be found from a single record".to_string(),
+                ))
+            }
+        },
+    };
+    if interval <= Duration::zero() || interval > Duration::days(1) {
+        return Err(ClimateError::invalid_value(format!(
+            "Logging interval of {} seconds must be between zero and a day",
+            interval.num_seconds()
+        )));
+    }
+    let expected = (Duration::days(1).num_seconds() / interval.num_seconds()) as f64;
+
+    let mut days: BTreeMap<NaiveDate, Vec<Vec<f64>>> = BTreeMap::new();
+    for (timestamp, _, values) in rows {
+        // the record closes its interval, so it belongs to the day the interval started in
+        let day = days
+            .entry((timestamp - interval).date())
+            .or_insert_with(|| vec![Vec::new(); fields.len()]);
+        for (i, value) in values.into_iter().enumerate() {
+            if let Some(value) = value {
+                day[i].push(value);
+            }
+        }
+    }
+
+    for (date, values) in days {
+        let mut daily: [Option<(f64, String)>; 10] = Default::default();
+        for (field, values) in fields.iter().zip(values) {
+            if (values.len() as f64) < mapping.min_completeness * expected {
+                continue;
+            }
+            let count = values.len() as f64;
+            let value = match field.aggregation {
+                Aggregation::Min => values.iter().cloned().fold(f64::INFINITY, f64::min),
+                Aggregation::Max => values.iter().cloned().fold(f64::NEG_INFINITY, f64::max),
+                Aggregation::Mean => values.iter().sum::<f64>() / count,
+                Aggregation::Sum => values.iter().sum::<f64>(),
+            };
+            let (value, units) = match (field.variable, &field.units) {
+                (Variable::Rs, units) => {
+                    // the mean total per interval times the intervals in a day is the day's total
+                    let value = match units.dimension() {
+                        Dimension::RadiantExposure => value * expected,
+                        _ => value,
+                    };
+                    let value = match RadiantExposureUnits::try_from(*units).and_then(|units| {
+                        RadiantExposure::new(value, units)
+                            .over(Period::Day)
+                            .value_in(RadiantExposureUnits::MegaJoulesPerSquareMeterPerDay)
+                    }) {
+                        Ok(value) => value,
+                        Err(e) => {
+                            report
+                                .errors
+                                .push(e.with_context(Variable::Rs.name(), date));
+                            continue;
+                        }
+                    };
+                    (
+                        value,
+                        Units::MegaJoulesPerSquareMeterPerDay.to_abbreviation(),
+                    )
+                }
+                (_, units) => (value, units.to_abbreviation()),
+            };
+            let position = Variable::all()
+                .iter()
+                .position(|v| *v == field.variable)
+                .unwrap();
+            daily[position] = Some((value, units.to_string()));
+        }
+
+        let [tmin, tmax, rhmin, rhmax, dewpoint, precip, rs, ea, wind_speed, pressure] = daily;
+        let (tmin, tmax) = match (tmin, tmax) {
+            (Some(tmin), Some(tmax)) => (tmin, tmax),
+            _ => {
+                report.incomplete_days.push(date);
+                continue;
+            }
+        };
+        let daily_data = DailyData::new(
+            date, tmin, tmax, rhmin, rhmax, dewpoint, precip, rs, ea, wind_speed,
+        )
+        .and_then(|daily_data| match pressure {
+            Some(pressure) => daily_data.with_pressure(pressure),
+            None => Ok(daily_data),
+        });
+        match daily_data {
+            Ok(daily_data) => {
+                station.daily_data.push(daily_data);
+                report.records_added += 1;
+            }
+            Err(e) => report.errors.push(e),
+        }
+    }
+
+    Ok(report)
+}
+
+/// Parses a TOA5 timestamp, with or without fractional seconds.
+fn parse_timestamp(value: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(value.trim(), "%Y-%m-%d %H:%M:%S%.f").ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn toa5(hours: u32, skip: &[u32]) -> String {
+        let mut data = String::from(
+            "\"TOA5\",\"Clay Center\",\"CR1000X\",\"12345\",\"CR1000X.Std.05.02\",\"CPU:met.CR1X\",\"4021\",\"Hourly\"\n\
+             \"TIMESTAMP\",\"RECORD\",\"AirTC_Max\",\"AirTC_Min\",\"RH\",\"SlrW_Avg\",\"Rain_in_Tot\",\"WS_ms_Avg\"\n\
+             \"TS\",\"RN\",\"Deg C\",\"Deg C\",\"%\",\"W/m^2\",\"inch\",\"meters/second\"\n\
+             \"\",\"\",\"Max\",\"Min\",\"Smp\",\"Avg\",\"Tot\",\"Avg\"\n",
+        );
+        let start = NaiveDate::from_ymd_opt(2023, 7, 1)
+            .unwrap()
+            .and_hms_opt(1, 0, 0)
+            .unwrap();
+        for hour in 0..hours {
+            if skip.contains(&hour) {
+                continue;
+            }
+            let timestamp = start + Duration::hours(hour as i64);
+            let h = (hour % 24) as f64;
+            let rain = if hour == 5 { "0.1" } else { "0" };
+            data.push_str(&format!(
+                "\"{}\",{},{},{},{},{},{},{}\n",
+                timestamp.format("%Y-%m-%d %H:%M:%S"),
+                hour,
+                20.0 + h / 2.0,
+                19.0 + h / 2.0,
+                40.0 + h,
+                if (6..18).contains(&(hour % 24)) {
+                    "600"
+                } else {
+                    "0"
+                },
+                rain,
+                if hour == 30 { "NAN" } else { "2.5" },
+            ));
+        }
+        data
+    }
+
+    fn station() -> StationData {
+        StationData::new(
+            "Clay Center".to_string(),
+            "TOA5".to_string(),
+            40.5,
+            -98.0,
+            531.0,
+            2.0,
+            None,
+        )
+    }
+
+    #[test]
+    fn aggregates_hourly_records() {
+        let mut station = station();
+        let data = toa5(48, &[]);
+        let report = read_toa5(
+            data.as_bytes(),
+            &mut station,
+            &Toa5Mapping {
+                tmin: "AirTC_Min".to_string(),
+                rhmin: Some("RH".to_string()),
+                rhmax: Some("RH".to_string()),
+                precip: Some("Rain_in_Tot".to_string()),
+                ..Toa5Mapping::default()
+            },
+        )
+        .unwrap();
+
+        assert_eq!(report.header.station_name, "Clay Center");
+        assert_eq!(report.header.table_name, "Hourly");
+        assert_eq!(report.records_added, 2);
+        assert!(report.errors.is_empty());
+
+        // the first day runs from the 01:00 record through the 00:00 record on July 2nd
+        let first = &station.daily_data[0];
+        assert_eq!(first.date, NaiveDate::from_ymd_opt(2023, 7, 1).unwrap());
+        assert_eq!(first.tmin, 19.0);
+        assert_eq!(first.tmax, 31.5);
+        assert_eq!(first.tmax_units, "°C");
+        assert_eq!((first.rhmin, first.rhmax), (Some(40.0), Some(63.0)));
+        assert_eq!(first.precip, Some(0.1));
+        assert_eq!(first.precip_units.as_deref(), Some("in"));
+        assert!((first.rs.unwrap() - 12.0 * 600.0 * 3600.0 / 1e6).abs() < 1e-9);
+        assert_eq!(first.rs_units.as_deref(), Some("MJ/m²/day"));
+        assert_eq!(first.wind_speed, Some(2.5));
+    }
+
+    #[test]
+    fn repeated_timestamps_are_reported() {
+        let mut station = station();
+        // a re-download repeats the 03:00 record with a different tmax
+        let mut data = toa5(48, &[]);
+        data.push_str("\"2023-07-01 03:00:00\",2,99,20,42,0,0,2.5\n");
+        let mapping = Toa5Mapping {
+            rhmin: None,
+            rhmax: None,
+            precip: None,
+            ..Toa5Mapping::default()
+        };
+        let report = read_toa5(data.as_bytes(), &mut station, &mapping).unwrap();
+
+        assert_eq!(report.records_added, 2);
+        assert_eq!(station.daily_data[0].tmax, 31.5);
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(
+            report.errors[0].to_string(),
+            "Timestamp '2023-07-01 03:00:00' repeats the record at row 7, which is kept at row 53 \
+             in column TIMESTAMP"
+        );
+    }
+
+    #[test]
+    fn incomplete_days_are_skipped() {
+        let mut station = station();
+        // the second day is missing four hours of temperature, more than 10%
+        let data = toa5(48, &[30, 31, 32, 33]);
+        let report = read_toa5(
+            data.as_bytes(),
+            &mut station,
+            &Toa5Mapping {
+                rhmin: None,
+                rhmax: None,
+                precip: None,
+                ..Toa5Mapping::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(report.records_added, 1);
+        assert_eq!(
+            report.incomplete_days,
+            vec![NaiveDate::from_ymd_opt(2023, 7, 2).unwrap()]
+        );
+
+        let error = read_toa5(
+            data.as_bytes(),
+            &mut station,
+            &Toa5Mapping {
+                tmin: "RH".to_string(),
+                ..Toa5Mapping::default()
+            },
+        )
+        .unwrap_err();
+        assert_eq!(error.variable(), Some("tmin"));
+    }
+}
@@ -0,0 +1,428 @@
+//! ASCE-EWRI (2005) standardized reference evapotranspiration for daily time steps, computed from
+//! the SI values held in `Output`.
+
+use crate::data_model::Variable;
+use crate::equations::{
+    atmospheric_pressure, clear_sky_radiation, cloudiness_function, extraterrestrial_radiation,
+    hourly_extraterrestrial_radiation, hourly_net_longwave_radiation, net_longwave_radiation,
+    psychrometric_constant, saturation_slope, saturation_vapor_pressure, sin_sun_angle,
+    solar_time_angle, vapor_pressure_from_rh, wind_speed_at_2m,
+};
+use crate::error::ClimateError;
+use crate::output::{HourlyOutput, Output};
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, Timelike};
+
+/// Albedo of the reference surface
+const ALBEDO: f64 = 0.23;
+
+/// Sun angle in radians above which the hourly cloudiness function is computed from Rs/Rso.
+const MIN_SUN_ANGLE: f64 = 0.3;
+
+/// The reference surface, each with its own numerator and denominator constants (Table 1).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReferenceSurface {
+    /// clipped grass, ETos
+    Short,
+    /// alfalfa, ETrs
+    Tall,
+}
+
+impl ReferenceSurface {
+    /// The numerator constant Cn and denominator constant Cd for a daily time step.
+    pub fn daily_constants(&self) -> (f64, f64) {
+        match self {
+            ReferenceSurface::Short => (900.0, 0.34),
+            ReferenceSurface::Tall => (1600.0, 0.38),
+        }
+    }
+
+    /// The numerator constant Cn, denominator constant Cd and soil heat flux as a fraction of Rn
+    /// for an hourly time step, daytime being when Rn is positive.
+    pub fn hourly_constants(&self, daytime: bool) -> (f64, f64, f64) {
+        match (self, daytime) {
+            (ReferenceSurface::Short, true) => (37.0, 0.24, 0.1),
+            (ReferenceSurface::Short, false) => (37.0, 0.96, 0.5),
+            (ReferenceSurface::Tall, true) => (66.0, 0.25, 0.04),
+            (ReferenceSurface::Tall, false) => (66.0, 1.7, 0.2),
+        }
+    }
+}
+
+/// The intermediate terms of the Penman-Monteith equation, kept for debugging. Radiation is in
+/// MJ/m² for the time step, pressure in kPa, temperature in Celsius and wind speed in m/s. For an
+/// hourly time step `tmean` is the hourly temperature.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RefEtTerms {
+    pub tmean: f64,
+    pub pressure: f64,
+    pub gamma: f64,
+    pub delta: f64,
+    pub es: f64,
+    pub ea: f64,
+    pub vpd: f64,
+    pub ra: f64,
+    pub rso: f64,
+    pub fcd: f64,
+    pub rns: f64,
+    pub rnl: f64,
+    pub rn: f64,
+    pub u2: f64,
+}
+
+/// Daily standardized reference ET in mm/day for both surfaces.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RefEt {
+    pub etos: f64,
+    pub etrs: f64,
+    pub terms: RefEtTerms,
+}
+
+/// Computes the daily ETos and ETrs for a single `Output`. Rs and wind speed are required, humidity
+/// is taken from ea, then dewpoint, then rhmax and rhmin, then rhmax alone.
+pub fn daily_refet(output: &Output) -> Result<RefEt, ClimateError> {
+    let date = output.get_date();
+    let missing = |variable: Variable| {
+        ClimateError::invalid_value(format!("{} is required for reference ET", variable.name()))
+            .with_context(variable.name(), date)
+    };
+
+    let tmin = output.get_tmin();
+    let tmax = output.get_tmax();
+    let rs = output.get_rs().ok_or_else(|| missing(Variable::Rs))?;
+    let ws = output
+        .get_ws()
+        .ok_or_else(|| missing(Variable::WindSpeed))?;
+    let ea = actual_vapor_pressure(output).ok_or_else(|| missing(Variable::Ea))?;
+
+    let tmean = (tmax + tmin) / 2.0;
+    let pressure = output
+        .get_pressure()
+        .unwrap_or_else(|| atmospheric_pressure(output.get_z()));
+    let gamma = psychrometric_constant(pressure);
+    let delta = saturation_slope(tmean);
+    let es = (saturation_vapor_pressure(tmax) + saturation_vapor_pressure(tmin)) / 2.0;
+
+    let ra = extraterrestrial_radiation(output.get_latitude(), date.ordinal());
+    let rso = clear_sky_radiation(ra, output.get_z());
+    let fcd = cloudiness_function(rs, rso);
+    let rns = (1.0 - ALBEDO) * rs;
+    let rnl = net_longwave_radiation(tmin, tmax, ea, fcd);
+    let rn = rns - rnl;
+    // outputs from `StationData::to_output` already hold ws at 2 m, other heights are adjusted
+    let u2 = wind_speed_at_2m(ws, output.get_wz());
+
+    let terms = RefEtTerms {
+        tmean,
+        pressure,
+        gamma,
+        delta,
+        es,
+        ea,
+        vpd: es - ea,
+        ra,
+        rso,
+        fcd,
+        rns,
+        rnl,
+        rn,
+        u2,
+    };
+
+    Ok(RefEt {
+        etos: penman_monteith(&terms, ReferenceSurface::Short),
+        etrs: penman_monteith(&terms, ReferenceSurface::Tall),
+        terms,
+    })
+}
+
+/// Hourly standardized reference ET in mm/h for both surfaces, `datetime` is the end of the hour.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HourlyRefEt {
+    pub datetime: NaiveDateTime,
+    pub etos: f64,
+    pub etrs: f64,
+    pub daytime: bool,
+    pub terms: RefEtTerms,
+}
+
+/// Hourly reference ET summed over a day, to compare against the daily method.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SummedRefEt {
+    pub date: NaiveDate,
+    pub etos: f64,
+    pub etrs: f64,
+    /// the number of hours in the sums
+    pub hours: usize,
+}
+
+/// The standardized reference ET equation (Eq. 1) with soil heat flux taken as zero for a day.
+pub fn penman_monteith(terms: &RefEtTerms, surface: ReferenceSurface) -> f64 {
+    let (cn, cd) = surface.daily_constants();
+    standardized_refet(terms, cn, cd, 0.0)
+}
+
+/// The standardized reference ET equation (Eq. 1) for an hour, with the day or night constants
+/// and soil heat flux.
+pub fn hourly_penman_monteith(terms: &RefEtTerms, surface: ReferenceSurface, daytime: bool) -> f64 {
+    let (cn, cd, g_ratio) = surface.hourly_constants(daytime);
+    standardized_refet(terms, cn, cd, g_ratio * terms.rn)
+}
+
+fn standardized_refet(terms: &RefEtTerms, cn: f64, cd: f64, g: f64) -> f64 {
+    let numerator = 0.408 * terms.delta * (terms.rn - g)
+        + terms.gamma * cn / (terms.tmean + 273.0) * terms.u2 * terms.vpd;
+    let denominator = terms.delta + terms.gamma * (1.0 + cd * terms.u2);
+    (numerator / denominator).max(0.0)
+}
+
+/// Computes the hourly ETos and ETrs for each output, returned in the same order. Rs and wind
+/// speed are required, humidity is taken from ea, then dewpoint, then rh.
+///
+/// The cloudiness function is computed from Rs/Rso while the sun is more than 0.3 radians above
+/// the horizon and carried forward through the evening and night from the last hour it was;
+/// hours before the first such hour use the first value. The solar time angle uses the output's
+/// standard meridian, or the nearest multiple of 15° to its longitude when that isn't set.
+pub fn hourly_refet(outputs: &[HourlyOutput]) -> Vec<Result<HourlyRefEt, ClimateError>> {
+    let mut order: Vec<usize> = (0..outputs.len()).collect();
+    order.sort_by_key(|i| outputs[*i].get_datetime());
+
+    // the radiation terms, with fcd only where the sun is high enough to compute it
+    let radiation: Vec<Option<(f64, f64, Option<f64>)>> = outputs
+        .iter()
+        .map(|output| {
+            let rs = output.get_rs()?;
+            let midpoint = output.get_datetime() - Duration::minutes(30);
+            let day_of_year = midpoint.ordinal();
+            let meridian = output
+                .get_standard_meridian()
+                .unwrap_or((output.get_longitude() / 15.0).round() * 15.0);
+            let standard_time = midpoint.hour() as f64 + midpoint.minute() as f64 / 60.0;
+            let omega =
+                solar_time_angle(standard_time, output.get_longitude(), meridian, day_of_year);
+            let latitude = output.get_latitude();
+            let ra = hourly_extraterrestrial_radiation(latitude, day_of_year, omega);
+            let rso = clear_sky_radiation(ra, output.get_z());
+            let fcd = (sin_sun_angle(latitude, day_of_year, omega) > MIN_SUN_ANGLE.sin())
+                .then(|| cloudiness_function(rs, rso));
+            Some((ra, rso, fcd))
+        })
+        .collect();
+
+    let mut fcd = vec![None; outputs.len()];
+    let mut carried = order
+        .iter()
+        .find_map(|i| radiation[*i].and_then(|(_, _, fcd)| fcd));
+    for i in &order {
+        if let Some((_, _, Some(value))) = radiation[*i] {
+            carried = Some(value);
+        }
+        fcd[*i] = carried;
+    }
+
+    outputs
+        .iter()
+        .zip(radiation)
+        .zip(fcd)
+        .map(|((output, radiation), fcd)| {
+            let date = output.get_datetime().date();
+            let missing = |name: &str| {
+                ClimateError::invalid_value(format!("{} is required for reference ET", name))
+                    .with_context(name, date)
+            };
+            let (ra, rso, _) = radiation.ok_or_else(|| missing(Variable::Rs.name()))?;
+            let fcd = fcd.ok_or_else(|| {
+                ClimateError::invalid_value(
+                    "No daylight hour to compute the cloudiness function from".to_string(),
+                )
+                .with_context(Variable::Rs.name(), date)
+            })?;
+            let rs = output.get_rs().unwrap_or_default();
+            let ws = output
+                .get_ws()
+                .ok_or_else(|| missing(Variable::WindSpeed.name()))?;
+            let temperature = output.get_temperature();
+            let ea = hourly_vapor_pressure(output).ok_or_else(|| missing(Variable::Ea.name()))?;
+
+            let pressure = atmospheric_pressure(output.get_z());
+            let es = saturation_vapor_pressure(temperature);
+            let rns = (1.0 - ALBEDO) * rs;
+            let rnl = hourly_net_longwave_radiation(temperature, ea, fcd);
+            let terms = RefEtTerms {
+                tmean: temperature,
+                pressure,
+                gamma: psychrometric_constant(pressure),
+                delta: saturation_slope(temperature),
+                es,
+                ea,
+                vpd: es - ea,
+                ra,
+                rso,
+                fcd,
+                rns,
+                rnl,
+                rn: rns - rnl,
+                u2: wind_speed_at_2m(ws, output.get_wz()),
+            };
+            let daytime = terms.rn > 0.0;
+
+            Ok(HourlyRefEt {
+                datetime: output.get_datetime(),
+                etos: hourly_penman_monteith(&terms, ReferenceSurface::Short, daytime),
+                etrs: hourly_penman_monteith(&terms, ReferenceSurface::Tall, daytime),
+                daytime,
+                terms,
+            })
+        })
+        .collect()
+}
+
+/// Sums hourly reference ET to daily totals in date order, the hour ending at midnight closes the
+/// day before.
+pub fn daily_sums(hourly: &[HourlyRefEt]) -> Vec<SummedRefEt> {
+    let mut sums: Vec<SummedRefEt> = Vec::new();
+    let mut hourly: Vec<&HourlyRefEt> = hourly.iter().collect();
+    hourly.sort_by_key(|refet| refet.datetime);
+    for refet in hourly {
+        let date = (refet.datetime - Duration::hours(1)).date();
+        match sums.last_mut() {
+            Some(sum) if sum.date == date => {
+                sum.etos += refet.etos;
+                sum.etrs += refet.etrs;
+                sum.hours += 1;
+            }
+            _ => sums.push(SummedRefEt {
+                date,
+                etos: refet.etos,
+                etrs: refet.etrs,
+                hours: 1,
+            }),
+        }
+    }
+    sums
+}
+
+/// Actual vapor pressure in kPa from the best humidity data available in the output.
+fn actual_vapor_pressure(output: &Output) -> Option<f64> {
+    if let Some(ea) = output.get_ea() {
+        return Some(ea);
+    }
+    if let Some(dewpoint) = output.get_dewpoint() {
+        return Some(saturation_vapor_pressure(dewpoint));
+    }
+    match (output.get_rhmin(), output.get_rhmax()) {
+        (Some(rhmin), Some(rhmax)) => Some(vapor_pressure_from_rh(
+            output.get_tmin(),
+            output.get_tmax(),
+            rhmin,
+            rhmax,
+        )),
+        // Eq. 12, when only rhmax is reliable
+        (None, Some(rhmax)) => Some(saturation_vapor_pressure(output.get_tmin()) * rhmax / 100.0),
+        _ => None,
+    }
+}
+
+/// Actual vapor pressure in kPa for an hour from the best humidity data available.
+fn hourly_vapor_pressure(output: &HourlyOutput) -> Option<f64> {
+    if let Some(ea) = output.get_ea() {
+        return Some(ea);
+    }
+    if let Some(dewpoint) = output.get_dewpoint() {
+        return Some(saturation_vapor_pressure(dewpoint));
+    }
+    output
+        .get_rh()
+        .map(|rh| saturation_vapor_pressure(output.get_temperature()) * rh / 100.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn round(value: f64, places: i32) -> f64 {
+        let factor = 10_f64.powi(places);
+        (value * factor).round() / factor
+    }
+
+    #[test]
+    fn daily_reference_et() {
+        // FAO-56 Example 18, Brussels on July 6th with 10 km/h wind measured at 10 m
+        let mut output = Output::new();
+        output.set_date(NaiveDate::from_ymd_opt(2023, 7, 6).unwrap());
+        output.set_tmax(21.5);
+        output.set_tmin(12.3);
+        output.set_rhmax(Some(84.0));
+        output.set_rhmin(Some(63.0));
+        output.set_rs(Some(22.07));
+        output.set_ws(Some(10.0 / 3.6));
+        output.set_wz(Some(10.0));
+        output.set_z(100.0);
+        output.set_latitude(50.8);
+
+        let refet = daily_refet(&output).unwrap();
+        let terms = &refet.terms;
+        assert_eq!(round(terms.ra, 1), 41.1);
+        assert_eq!(round(terms.es, 2), 2.0);
+        assert_eq!(round(terms.ea, 2), 1.41);
+        assert_eq!(round(terms.u2, 2), 2.08);
+        assert_eq!(round(terms.rnl, 1), 3.7);
+        assert_eq!(round(terms.rn, 1), 13.3);
+        assert_eq!(round(refet.etos, 1), 3.9);
+        assert!(refet.etrs > refet.etos);
+
+        output.set_rs(None);
+        let error = daily_refet(&output).err().unwrap();
+        assert_eq!(error.variable(), Some("rs"));
+    }
+
+    #[test]
+    fn hourly_reference_et() {
+        // FAO-56 Example 19, N'Diaye on October 1st from 14:00 to 15:00, followed by a night hour
+        let mut output = HourlyOutput::new();
+        let datetime = NaiveDate::from_ymd_opt(2023, 10, 1)
+            .unwrap()
+            .and_hms_opt(15, 0, 0)
+            .unwrap();
+        output.set_datetime(datetime);
+        output.set_temperature(38.0);
+        output.set_rh(Some(52.0));
+        output.set_rs(Some(2.45));
+        output.set_ws(Some(3.3));
+        output.set_z(8.0);
+        output.set_latitude(16.217);
+        output.set_longitude(-16.25);
+        output.set_standard_meridian(Some(-15.0));
+
+        let mut night = HourlyOutput::new();
+        night.set_datetime(datetime + Duration::hours(8));
+        night.set_temperature(28.0);
+        night.set_rh(Some(80.0));
+        night.set_rs(Some(0.0));
+        night.set_ws(Some(1.0));
+        night.set_z(8.0);
+        night.set_latitude(16.217);
+        night.set_longitude(-16.25);
+
+        let results = hourly_refet(&[night, output]);
+        let night = results[0].as_ref().unwrap();
+        let day = results[1].as_ref().unwrap();
+        assert_eq!(round(day.terms.ra, 2), 3.54);
+        assert_eq!(round(day.terms.rso, 2), 2.66);
+        assert_eq!(round(day.terms.fcd, 2), 0.89);
+        assert_eq!(round(day.terms.rn, 2), 1.75);
+        assert!(day.daytime);
+        assert_eq!(round(day.etos, 2), 0.66);
+        assert!(day.etrs > day.etos);
+
+        // the night hour carries the afternoon's cloudiness and uses the night constants
+        assert_eq!(night.terms.fcd, day.terms.fcd);
+        assert!(!night.daytime);
+        assert_eq!(night.terms.rso, 0.0);
+
+        let sums = daily_sums(&[night.clone(), day.clone()]);
+        assert_eq!(sums.len(), 1);
+        assert_eq!(sums[0].hours, 2);
+        assert_eq!(sums[0].etos, day.etos + night.etos);
+    }
+}
@@ -1,6 +1,15 @@
+use crate::error::ClimateError;
+use std::collections::HashMap;
+use std::sync::{OnceLock, RwLock};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Units {
     Celsius,
     Fahrenheit,
+    Kelvin,
+    CelsiusDifference,
+    FahrenheitDifference,
+    KelvinDifference,
     Millimeters,
     Centimeters,
     Meters,
@@ -12,12 +21,25 @@ pub enum Units {
     Langley,
     MegaJoulesPerSquareMeter,
     WattsPerSquareMeter,
+    LangleysPerDay,
+    LangleysPerHour,
+    MegaJoulesPerSquareMeterPerDay,
+    MegaJoulesPerSquareMeterPerHour,
     Pascals,
     KiloPascals,
+    Hectopascals,
+    Millibars,
+    InchesOfMercury,
+    MillimetersOfMercury,
     Degrees,
     Radians,
     MetersPerSecond,
     MilesPerHour,
+    KilometersPerHour,
+    FeetPerSecond,
+    Knots,
+    KilometersPerDay,
+    MilesPerDay,
     Acres,
     Hectares,
     SquareFeet,
@@ -30,6 +52,10 @@ impl Units {
         match self {
             Units::Celsius => "°C",
             Units::Fahrenheit => "°F",
+            Units::Kelvin => "K",
+            Units::CelsiusDifference => "Δ°C",
+            Units::FahrenheitDifference => "Δ°F",
+            Units::KelvinDifference => "ΔK",
             Units::Millimeters => "mm",
             Units::Centimeters => "cm",
             Units::Meters => "m",
@@ -41,12 +67,25 @@ impl Units {
             Units::Langley => "L",
             Units::MegaJoulesPerSquareMeter => "MJ/m²",
             Units::WattsPerSquareMeter => "W/m²",
+            Units::LangleysPerDay => "L/day",
+            Units::LangleysPerHour => "L/h",
+            Units::MegaJoulesPerSquareMeterPerDay => "MJ/m²/day",
+            Units::MegaJoulesPerSquareMeterPerHour => "MJ/m²/h",
             Units::Pascals => "Pa",
             Units::KiloPascals => "kPa",
+            Units::Hectopascals => "hPa",
+            Units::Millibars => "mbar",
+            Units::InchesOfMercury => "inHg",
+            Units::MillimetersOfMercury => "mmHg",
             Units::Degrees => "°",
             Units::Radians => "rad",
             Units::MetersPerSecond => "m/s",
             Units::MilesPerHour => "mph",
+            Units::KilometersPerHour => "km/h",
+            Units::FeetPerSecond => "ft/s",
+            Units::Knots => "kn",
+            Units::KilometersPerDay => "km/day",
+            Units::MilesPerDay => "mi/day",
             Units::Acres => "acres",
             Units::Hectares => "ha",
             Units::SquareFeet => "ft²",
@@ -55,65 +94,92 @@ impl Units {
         }
     }
 
-    pub fn from_abbreviation(abbreviation: &str) -> Result<Units, String> {
-        match abbreviation {
-            "°C" => Ok(Units::Celsius),
-            "C" => Ok(Units::Celsius),
-            "c" => Ok(Units::Celsius),
-            "°F" => Ok(Units::Fahrenheit),
-            "F" => Ok(Units::Fahrenheit),
-            "f" => Ok(Units::Fahrenheit),
-            "mm" => Ok(Units::Millimeters),
-            "cm" => Ok(Units::Centimeters),
-            "m" => Ok(Units::Meters),
-            "km" => Ok(Units::Kilometers),
-            "KM" => Ok(Units::Kilometers),
-            "in" => Ok(Units::Inches),
-            "ft" => Ok(Units::Feet),
-            "yd" => Ok(Units::Yards),
-            "mi" => Ok(Units::Miles),
-            "L" => Ok(Units::Langley),
-            "MJ/m²" => Ok(Units::MegaJoulesPerSquareMeter),
-            "mj/m²" => Ok(Units::MegaJoulesPerSquareMeter),
-            "mj/m2" => Ok(Units::MegaJoulesPerSquareMeter),
-            "mj/m^2" => Ok(Units::MegaJoulesPerSquareMeter),
-            "mJ/m^2" => Ok(Units::MegaJoulesPerSquareMeter),
-            "W/m²" => Ok(Units::WattsPerSquareMeter),
-            "w/m²" => Ok(Units::WattsPerSquareMeter),
-            "W/m-2" => Ok(Units::WattsPerSquareMeter),
-            "w/m-2" => Ok(Units::WattsPerSquareMeter),
-            "Pa" => Ok(Units::Pascals),
-            "pa" => Ok(Units::Pascals),
-            "kpa" => Ok(Units::KiloPascals),
-            "kPa" => Ok(Units::KiloPascals),
-            "KPA" => Ok(Units::KiloPascals),
-            "KPa" => Ok(Units::KiloPascals),
-            "°" => Ok(Units::Degrees),
-            "deg" => Ok(Units::Degrees),
-            "rad" => Ok(Units::Radians),
-            "m/s" => Ok(Units::MetersPerSecond),
-            "mph" => Ok(Units::MilesPerHour),
-            "acres" => Ok(Units::Acres),
-            "ha" => Ok(Units::Hectares),
-            "ft²" => Ok(Units::SquareFeet),
-            "sq ft" => Ok(Units::SquareFeet),
-            "ft2" => Ok(Units::SquareFeet),
-            "m²" => Ok(Units::SquareMeters),
-            "sq m" => Ok(Units::SquareMeters),
-            "m2" => Ok(Units::SquareMeters),
-            "%" => Ok(Units::Percent),
-            "percent" => Ok(Units::Percent),
-            "Percent" => Ok(Units::Percent),
-            "degC" => Ok(Units::Celsius),
-            "degF" => Ok(Units::Fahrenheit),
-            _ => Err(format!("Invalid unit: {}", abbreviation)),
+    /// Reads units from how they are commonly written. Case, whitespace, superscripts or `^2`,
+    /// CF exponents such as `W m-2` and words such as `inches` or `meters per second` are
+    /// accepted, as are aliases added with `Units::register_alias`. Unknown units are an error
+    /// that suggests the closest known unit.
+    pub fn from_abbreviation(abbreviation: &str) -> Result<Units, ClimateError> {
+        let key = normalize(abbreviation);
+        let registered = registered_aliases()
+            .read()
+            .map(|aliases| aliases.get(&key).copied())
+            .unwrap_or(None);
+        if let Some(units) = registered.or_else(|| builtin_aliases().get(&key).copied()) {
+            return Ok(units);
+        }
+
+        Err(ClimateError::UnknownUnit {
+            unit: abbreviation.to_string(),
+            suggestion: suggestion(&key),
+            variable: None,
+            date: None,
+        })
+    }
+
+    /// Adds an alias for units that `from_abbreviation` will accept from then on, such as a
+    /// project's own spelling. Aliases are normalized like any other units and take precedence
+    /// over the built in ones.
+    pub fn register_alias(alias: &str, units: Units) {
+        if let Ok(mut aliases) = registered_aliases().write() {
+            aliases.insert(normalize(alias), units);
         }
     }
 
+    /// Every unit, in declaration order.
+    pub fn all() -> &'static [Units] {
+        &[
+            Units::Celsius,
+            Units::Fahrenheit,
+            Units::Kelvin,
+            Units::CelsiusDifference,
+            Units::FahrenheitDifference,
+            Units::KelvinDifference,
+            Units::Millimeters,
+            Units::Centimeters,
+            Units::Meters,
+            Units::Kilometers,
+            Units::Inches,
+            Units::Feet,
+            Units::Yards,
+            Units::Miles,
+            Units::Langley,
+            Units::MegaJoulesPerSquareMeter,
+            Units::WattsPerSquareMeter,
+            Units::LangleysPerDay,
+            Units::LangleysPerHour,
+            Units::MegaJoulesPerSquareMeterPerDay,
+            Units::MegaJoulesPerSquareMeterPerHour,
+            Units::Pascals,
+            Units::KiloPascals,
+            Units::Hectopascals,
+            Units::Millibars,
+            Units::InchesOfMercury,
+            Units::MillimetersOfMercury,
+            Units::Degrees,
+            Units::Radians,
+            Units::MetersPerSecond,
+            Units::MilesPerHour,
+            Units::KilometersPerHour,
+            Units::FeetPerSecond,
+            Units::Knots,
+            Units::KilometersPerDay,
+            Units::MilesPerDay,
+            Units::Acres,
+            Units::Hectares,
+            Units::SquareFeet,
+            Units::SquareMeters,
+            Units::Percent,
+        ]
+    }
+
     pub fn name(&self) -> &str {
         match self {
             Units::Celsius => "Celsius",
             Units::Fahrenheit => "Fahrenheit",
+            Units::Kelvin => "Kelvin",
+            Units::CelsiusDifference => "Celsius Difference",
+            Units::FahrenheitDifference => "Fahrenheit Difference",
+            Units::KelvinDifference => "Kelvin Difference",
             Units::Millimeters => "Millimeters",
             Units::Centimeters => "Centimeters",
             Units::Meters => "Meters",
@@ -124,13 +190,26 @@ impl Units {
             Units::Miles => "Miles",
             Units::Langley => "Langley",
             Units::MegaJoulesPerSquareMeter => "MegaJoules/Meter²",
-            Units::WattsPerSquareMeter => "Watts",
+            Units::WattsPerSquareMeter => "Watts/Meter²",
+            Units::LangleysPerDay => "Langleys/Day",
+            Units::LangleysPerHour => "Langleys/Hour",
+            Units::MegaJoulesPerSquareMeterPerDay => "MegaJoules/Meter²/Day",
+            Units::MegaJoulesPerSquareMeterPerHour => "MegaJoules/Meter²/Hour",
             Units::Pascals => "Pascals",
             Units::KiloPascals => "KiloPascals",
+            Units::Hectopascals => "Hectopascals",
+            Units::Millibars => "Millibars",
+            Units::InchesOfMercury => "Inches of Mercury",
+            Units::MillimetersOfMercury => "Millimeters of Mercury",
             Units::Degrees => "Degrees",
             Units::Radians => "Radians",
             Units::MetersPerSecond => "Meters/Second",
             Units::MilesPerHour => "Miles/Hour",
+            Units::KilometersPerHour => "Kilometers/Hour",
+            Units::FeetPerSecond => "Feet/Second",
+            Units::Knots => "Knots",
+            Units::KilometersPerDay => "Kilometers/Day",
+            Units::MilesPerDay => "Miles/Day",
             Units::Acres => "Acres",
             Units::Hectares => "Hectares",
             Units::SquareFeet => "Square Feet",
@@ -139,50 +218,432 @@ impl Units {
         }
     }
 
-    // convert between different units, there are several special cases to consider where meters to meters per second conversion is based on
-    // total meters per day and will change to meters per second. Also miles (per day) to meters per second conversion is based on total miles per day.
-    pub fn convert(&self, value: f64, to_unit: &Units) -> Result<f64, String> {
-        match (self, to_unit) {
-            (Units::Celsius, Units::Fahrenheit) => Ok(value * 9.0 / 5.0 + 32.0),
-            (Units::Fahrenheit, Units::Celsius) => Ok((value - 32.0) * 5.0 / 9.0),
-            (Units::Millimeters, Units::Centimeters) => Ok(value / 10.0),
-            (Units::Centimeters, Units::Millimeters) => Ok(value * 10.0),
-            (Units::Meters, Units::Kilometers) => Ok(value / 1000.0),
-            (Units::Kilometers, Units::Meters) => Ok(value * 1000.0),
-            (Units::Inches, Units::Feet) => Ok(value / 12.0),
-            (Units::Feet, Units::Inches) => Ok(value * 12.0),
-            (Units::Yards, Units::Meters) => Ok(value * 0.9144),
-            (Units::Meters, Units::Yards) => Ok(value / 0.9144),
-            (Units::Miles, Units::Kilometers) => Ok(value * 1.60934),
-            (Units::Kilometers, Units::Miles) => Ok(value / 1.60934),
-            (Units::Langley, Units::MegaJoulesPerSquareMeter) => Ok(value * 0.04184),
-            (Units::MegaJoulesPerSquareMeter, Units::Langley) => Ok(value / 0.04184),
-            (Units::WattsPerSquareMeter, Units::MegaJoulesPerSquareMeter) => Ok(value / 3600000.0),
-            (Units::MegaJoulesPerSquareMeter, Units::WattsPerSquareMeter) => Ok(value * 3600000.0),
-            (Units::KiloPascals, Units::Pascals) => Ok(value * 1000.0),
-            (Units::Pascals, Units::KiloPascals) => Ok(value / 1000.0),
-            (Units::Degrees, Units::Radians) => Ok(value * std::f64::consts::PI / 180.0),
-            (Units::Radians, Units::Degrees) => Ok(value * 180.0 / std::f64::consts::PI),
-            (Units::MetersPerSecond, Units::MilesPerHour) => Ok(value * 2.23694),
-            (Units::MilesPerHour, Units::MetersPerSecond) => Ok(value / 2.23694),
-            (Units::Miles, Units::MetersPerSecond) => Ok(value * 0.01864352),
-            (Units::Meters, Units::MetersPerSecond) => Ok(value * 0.000011574074),  // assumes Meters per day
-            (Units::Kilometers, Units::MetersPerSecond) => Ok(value * 0.0011574074),  // assumes Kilometers per day
-            (Units::Acres, Units::SquareMeters) => Ok(value * 4046.86),
-            (Units::SquareMeters, Units::Acres) => Ok(value / 4046.86),
-            (Units::Hectares, Units::SquareMeters) => Ok(value * 10000.0),
-            (Units::SquareMeters, Units::Hectares) => Ok(value / 10000.0),
-            (Units::SquareFeet, Units::SquareMeters) => Ok(value / 10.7639),
-            (Units::SquareMeters, Units::SquareFeet) => Ok(value * 10.7639),
-            (Units::Hectares, Units::Acres) => Ok(value / 2.47105),
-            (Units::Acres, Units::Hectares) => Ok(value * 2.47105),
-            _ => Err(format!(
-                "Unsupported conversion from {} to {}",
+    /// The dimension the unit measures, units convert only to units of the same dimension.
+    pub fn dimension(&self) -> Dimension {
+        match self {
+            Units::Millimeters
+            | Units::Centimeters
+            | Units::Meters
+            | Units::Kilometers
+            | Units::Inches
+            | Units::Feet
+            | Units::Yards
+            | Units::Miles => Dimension::Length,
+            Units::Celsius | Units::Fahrenheit | Units::Kelvin => Dimension::Temperature,
+            Units::CelsiusDifference | Units::FahrenheitDifference | Units::KelvinDifference => {
+                Dimension::TemperatureDifference
+            }
+            Units::MetersPerSecond
+            | Units::MilesPerHour
+            | Units::KilometersPerHour
+            | Units::FeetPerSecond
+            | Units::Knots
+            | Units::KilometersPerDay
+            | Units::MilesPerDay => Dimension::Speed,
+            Units::Langley | Units::MegaJoulesPerSquareMeter => Dimension::RadiantExposure,
+            Units::WattsPerSquareMeter
+            | Units::LangleysPerDay
+            | Units::LangleysPerHour
+            | Units::MegaJoulesPerSquareMeterPerDay
+            | Units::MegaJoulesPerSquareMeterPerHour => Dimension::EnergyFlux,
+            Units::Pascals
+            | Units::KiloPascals
+            | Units::Hectopascals
+            | Units::Millibars
+            | Units::InchesOfMercury
+            | Units::MillimetersOfMercury => Dimension::Pressure,
+            Units::Acres | Units::Hectares | Units::SquareFeet | Units::SquareMeters => {
+                Dimension::Area
+            }
+            Units::Degrees | Units::Radians => Dimension::Angle,
+            Units::Percent => Dimension::Ratio,
+        }
+    }
+
+    /// Converts a value in this unit to the base unit of its dimension.
+    pub(crate) fn base_value(&self, value: f64) -> f64 {
+        match self {
+            Units::Millimeters => value / 1000.0,
+            Units::Centimeters => value / 100.0,
+            Units::Meters => value,
+            Units::Kilometers => value * 1000.0,
+            Units::Inches => value * 0.0254,
+            Units::Feet => value * 0.3048,
+            Units::Yards => value * 0.9144,
+            Units::Miles => value * 1609.344,
+            Units::Celsius => value,
+            Units::Fahrenheit => (value - 32.0) * 5.0 / 9.0,
+            Units::Kelvin => value - 273.15,
+            Units::CelsiusDifference | Units::KelvinDifference => value,
+            Units::FahrenheitDifference => value * 5.0 / 9.0,
+            Units::MetersPerSecond => value,
+            Units::MilesPerHour => value * 0.44704,
+            Units::KilometersPerHour => value / 3.6,
+            Units::FeetPerSecond => value * 0.3048,
+            Units::Knots => value * 1852.0 / SECONDS_PER_HOUR,
+            Units::KilometersPerDay => value * 1000.0 / SECONDS_PER_DAY,
+            Units::MilesPerDay => value * 1609.344 / SECONDS_PER_DAY,
+            Units::MegaJoulesPerSquareMeter => value,
+            Units::Langley => value * 0.04184,
+            Units::WattsPerSquareMeter => value,
+            Units::LangleysPerDay => value * 41840.0 / SECONDS_PER_DAY,
+            Units::LangleysPerHour => value * 41840.0 / SECONDS_PER_HOUR,
+            Units::MegaJoulesPerSquareMeterPerDay => value * 1e6 / SECONDS_PER_DAY,
+            Units::MegaJoulesPerSquareMeterPerHour => value * 1e6 / SECONDS_PER_HOUR,
+            Units::Pascals => value,
+            Units::KiloPascals => value * 1000.0,
+            Units::Hectopascals | Units::Millibars => value * 100.0,
+            Units::InchesOfMercury => value * 3386.389,
+            Units::MillimetersOfMercury => value * 133.322387415,
+            Units::SquareMeters => value,
+            Units::Acres => value * 4046.8564224,
+            Units::Hectares => value * 10000.0,
+            Units::SquareFeet => value * 0.09290304,
+            Units::Radians => value,
+            Units::Degrees => value * std::f64::consts::PI / 180.0,
+            Units::Percent => value,
+        }
+    }
+
+    /// Converts a value in the base unit of this unit's dimension to this unit.
+    fn value_from_base(&self, value: f64) -> f64 {
+        match self {
+            Units::Millimeters => value * 1000.0,
+            Units::Centimeters => value * 100.0,
+            Units::Meters => value,
+            Units::Kilometers => value / 1000.0,
+            Units::Inches => value / 0.0254,
+            Units::Feet => value / 0.3048,
+            Units::Yards => value / 0.9144,
+            Units::Miles => value / 1609.344,
+            Units::Celsius => value,
+            Units::Fahrenheit => value * 9.0 / 5.0 + 32.0,
+            Units::Kelvin => value + 273.15,
+            Units::CelsiusDifference | Units::KelvinDifference => value,
+            Units::FahrenheitDifference => value * 9.0 / 5.0,
+            Units::MetersPerSecond => value,
+            Units::MilesPerHour => value / 0.44704,
+            Units::KilometersPerHour => value * 3.6,
+            Units::FeetPerSecond => value / 0.3048,
+            Units::Knots => value * SECONDS_PER_HOUR / 1852.0,
+            Units::KilometersPerDay => value * SECONDS_PER_DAY / 1000.0,
+            Units::MilesPerDay => value * SECONDS_PER_DAY / 1609.344,
+            Units::MegaJoulesPerSquareMeter => value,
+            Units::Langley => value / 0.04184,
+            Units::WattsPerSquareMeter => value,
+            Units::LangleysPerDay => value * SECONDS_PER_DAY / 41840.0,
+            Units::LangleysPerHour => value * SECONDS_PER_HOUR / 41840.0,
+            Units::MegaJoulesPerSquareMeterPerDay => value * SECONDS_PER_DAY / 1e6,
+            Units::MegaJoulesPerSquareMeterPerHour => value * SECONDS_PER_HOUR / 1e6,
+            Units::Pascals => value,
+            Units::KiloPascals => value / 1000.0,
+            Units::Hectopascals | Units::Millibars => value / 100.0,
+            Units::InchesOfMercury => value / 3386.389,
+            Units::MillimetersOfMercury => value / 133.322387415,
+            Units::SquareMeters => value,
+            Units::Acres => value / 4046.8564224,
+            Units::Hectares => value / 10000.0,
+            Units::SquareFeet => value / 0.09290304,
+            Units::Radians => value,
+            Units::Degrees => value * 180.0 / std::f64::consts::PI,
+            Units::Percent => value,
+        }
+    }
+
+    /// Converts a value to another unit of the same dimension through the dimension's base unit.
+    pub fn convert(&self, value: f64, to_unit: &Units) -> Result<f64, ClimateError> {
+        match (self.dimension(), to_unit.dimension()) {
+            (from, to) if from == to => Ok(to_unit.value_from_base(self.base_value(value))),
+            (from, to) => Err(ClimateError::incompatible_dimensions(
                 self.name(),
-                to_unit.name()
+                from.name(),
+                to_unit.name(),
+                to.name(),
             )),
         }
     }
+
+    /// Reads a radiation total without a period, MJ/m² or Langleys, as accumulated over
+    /// `period`. Other units, including radiation that already has a period, are returned as is.
+    pub fn over(&self, period: Period) -> Units {
+        match (self, period) {
+            (Units::MegaJoulesPerSquareMeter, Period::Day) => Units::MegaJoulesPerSquareMeterPerDay,
+            (Units::MegaJoulesPerSquareMeter, Period::Hour) => {
+                Units::MegaJoulesPerSquareMeterPerHour
+            }
+            (Units::Langley, Period::Day) => Units::LangleysPerDay,
+            (Units::Langley, Period::Hour) => Units::LangleysPerHour,
+            (units, _) => *units,
+        }
+    }
+}
+
+/// Spellings accepted besides each unit's abbreviation and name and the words in `WORDS`.
+const ALIASES: &[(&str, Units)] = &[
+    ("kph", Units::KilometersPerHour),
+    ("sq ft", Units::SquareFeet),
+    ("sq m", Units::SquareMeters),
+    // a daily precipitation rate, or a mass of water per area, is the day's depth in mm
+    ("mm/day", Units::Millimeters),
+    ("kg m-2", Units::Millimeters),
+    ("kg m-2 d-1", Units::Millimeters),
+];
+
+/// Words that make up units, replaced within each part of the normalized text.
+const WORDS: &[(&str, &str)] = &[
+    ("c", "degc"),
+    ("celsius", "degc"),
+    ("degcelsius", "degc"),
+    ("f", "degf"),
+    ("fahrenheit", "degf"),
+    ("degfahrenheit", "degf"),
+    ("kelvin", "k"),
+    ("degk", "k"),
+    ("degrees", "deg"),
+    ("degree", "deg"),
+    ("millimeter", "mm"),
+    ("millimeters", "mm"),
+    ("millimetre", "mm"),
+    ("millimetres", "mm"),
+    ("centimeter", "cm"),
+    ("centimeters", "cm"),
+    ("meter", "m"),
+    ("meters", "m"),
+    ("metre", "m"),
+    ("metres", "m"),
+    ("kilometer", "km"),
+    ("kilometers", "km"),
+    ("inch", "in"),
+    ("inches", "in"),
+    ("foot", "ft"),
+    ("feet", "ft"),
+    ("yard", "yd"),
+    ("yards", "yd"),
+    ("mile", "mi"),
+    ("miles", "mi"),
+    ("second", "s"),
+    ("seconds", "s"),
+    ("sec", "s"),
+    ("hour", "h"),
+    ("hours", "h"),
+    ("hr", "h"),
+    ("d", "day"),
+    ("days", "day"),
+    ("ly", "l"),
+    ("langley", "l"),
+    ("langleys", "l"),
+    ("watt", "w"),
+    ("watts", "w"),
+    ("megajoule", "mj"),
+    ("megajoules", "mj"),
+    ("pascal", "pa"),
+    ("pascals", "pa"),
+    ("kilopascal", "kpa"),
+    ("kilopascals", "kpa"),
+    ("hectopascal", "hpa"),
+    ("hectopascals", "hpa"),
+    ("mb", "mbar"),
+    ("millibar", "mbar"),
+    ("millibars", "mbar"),
+    ("knot", "kn"),
+    ("knots", "kn"),
+    ("kt", "kn"),
+    ("kts", "kn"),
+    ("pct", "%"),
+];
+
+/// Reduces a unit string to a canonical form: lower case, without spaces, `°` written as `deg`,
+/// exponents as plain digits and every part divided by written after a `/`.
+fn normalize(units: &str) -> String {
+    let mut text = units.trim().to_lowercase();
+    for (from, to) in [
+        ("²", "2"),
+        ("³", "3"),
+        ("⁻", "-"),
+        ("¹", "1"),
+        ("^", ""),
+        ("**", ""),
+        ("·", " "),
+        ("*", " "),
+        ("_", " "),
+        (" per ", "/"),
+        ("delta ", "δ"),
+        ("degrees ", "deg"),
+        ("degree ", "deg"),
+        ("deg ", "deg"),
+        ("°", "deg"),
+    ] {
+        text = text.replace(from, to);
+    }
+
+    let mut numerator = String::new();
+    let mut denominators = Vec::new();
+    for token in text.split_whitespace() {
+        for (index, part) in token.split('/').enumerate() {
+            let part = part.trim_end_matches('.');
+            let split = part.trim_end_matches(|c: char| c.is_ascii_digit()).len();
+            let (word, exponent) = part.split_at(split);
+            let (word, negative) = match word.strip_suffix('-') {
+                Some(word) => (word, true),
+                None => (word, false),
+            };
+            let word = WORDS
+                .iter()
+                .find(|(from, _)| *from == word)
+                .map_or(word, |(_, to)| *to);
+            let exponent = if exponent == "1" { "" } else { exponent };
+            if index > 0 || negative {
+                denominators.push(format!("{}{}", word, exponent));
+            } else {
+                numerator.push_str(word);
+                numerator.push_str(exponent);
+            }
+        }
+    }
+    for denominator in denominators {
+        numerator.push('/');
+        numerator.push_str(&denominator);
+    }
+    numerator
+}
+
+/// The normalized abbreviations, names and aliases of every unit.
+fn builtin_aliases() -> &'static HashMap<String, Units> {
+    static ALIAS_MAP: OnceLock<HashMap<String, Units>> = OnceLock::new();
+    ALIAS_MAP.get_or_init(|| {
+        let mut aliases = HashMap::new();
+        for units in Units::all() {
+            aliases.insert(normalize(units.name()), *units);
+        }
+        for (alias, units) in ALIASES {
+            aliases.insert(normalize(alias), *units);
+        }
+        // abbreviations are added last so they win over a name that normalizes the same way
+        for units in Units::all() {
+            aliases.insert(normalize(units.to_abbreviation()), *units);
+        }
+        aliases
+    })
+}
+
+fn registered_aliases() -> &'static RwLock<HashMap<String, Units>> {
+    static REGISTERED: OnceLock<RwLock<HashMap<String, Units>>> = OnceLock::new();
+    REGISTERED.get_or_init(|| RwLock::new(HashMap::new()))
+}
+
+/// The abbreviation of the known unit closest to a normalized unit string, when one is close
+/// enough to be a likely typo.
+fn suggestion(key: &str) -> Option<String> {
+    let registered = registered_aliases()
+        .read()
+        .map(|aliases| aliases.clone())
+        .unwrap_or_default();
+    let names = Units::all()
+        .iter()
+        .map(|units| (units.name().to_lowercase(), *units));
+    let max_distance = (key.chars().count() / 3).max(1);
+    registered
+        .into_iter()
+        .chain(
+            builtin_aliases()
+                .iter()
+                .map(|(alias, units)| (alias.clone(), *units)),
+        )
+        .chain(names)
+        .map(|(alias, units)| (edit_distance(key, &alias), alias, units))
+        .filter(|(distance, _, _)| *distance <= max_distance)
+        .min_by(|a, b| (a.0, &a.1).cmp(&(b.0, &b.1)))
+        .map(|(_, _, units)| units.to_abbreviation().to_string())
+}
+
+/// The Levenshtein distance between two strings.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a != *b);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+const SECONDS_PER_DAY: f64 = 86400.0;
+const SECONDS_PER_HOUR: f64 = 3600.0;
+
+/// The period a radiation total is accumulated over.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Period {
+    Hour,
+    Day,
+}
+
+/// What a unit measures. Each dimension has a base unit that all of its units convert through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dimension {
+    Length,
+    Temperature,
+    /// a change in temperature, such as a lapse rate or an offset, which has no zero point
+    TemperatureDifference,
+    Speed,
+    EnergyFlux,
+    RadiantExposure,
+    Pressure,
+    Area,
+    Angle,
+    Ratio,
+}
+
+impl Dimension {
+    pub fn name(&self) -> &str {
+        match self {
+            Dimension::Length => "length",
+            Dimension::Temperature => "temperature",
+            Dimension::TemperatureDifference => "temperature difference",
+            Dimension::Speed => "speed",
+            Dimension::EnergyFlux => "energy flux",
+            Dimension::RadiantExposure => "radiant exposure",
+            Dimension::Pressure => "pressure",
+            Dimension::Area => "area",
+            Dimension::Angle => "angle",
+            Dimension::Ratio => "ratio",
+        }
+    }
+
+    /// The unit the dimension's conversions go through.
+    pub fn base_units(&self) -> Units {
+        match self {
+            Dimension::Length => Units::Meters,
+            Dimension::Temperature => Units::Celsius,
+            Dimension::TemperatureDifference => Units::CelsiusDifference,
+            Dimension::Speed => Units::MetersPerSecond,
+            Dimension::EnergyFlux => Units::WattsPerSquareMeter,
+            Dimension::RadiantExposure => Units::MegaJoulesPerSquareMeter,
+            Dimension::Pressure => Units::Pascals,
+            Dimension::Area => Units::SquareMeters,
+            Dimension::Angle => Units::Radians,
+            Dimension::Ratio => Units::Percent,
+        }
+    }
+}
+
+/// Units are written as their abbreviation and read back with `Units::from_abbreviation`.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Units {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.to_abbreviation())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Units {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Units, D::Error> {
+        let abbreviation = String::deserialize(deserializer)?;
+        Units::from_abbreviation(&abbreviation).map_err(serde::de::Error::custom)
+    }
 }
 
 #[cfg(test)]
@@ -201,10 +662,11 @@ mod tests {
 
         assert_eq!(
             celsius.convert(25.0, &meters),
-            Err(format!(
-                "Unsupported conversion from {} to {}",
-                celsius.name(),
-                meters.name()
+            Err(ClimateError::incompatible_dimensions(
+                "Celsius",
+                "temperature",
+                "Meters",
+                "length"
             ))
         );
         assert_eq!(meters.convert(25000.0, &kilometers), Ok(25.0));
@@ -247,4 +709,189 @@ mod tests {
             / 10000.0; // round to 4 decimal places
         assert_eq!(mps, 10.0);
     }
+
+    #[test]
+    fn conversions_go_through_the_base_unit() {
+        let round = |value: f64| (value * 1e6).round() / 1e6;
+        assert_eq!(
+            Units::Millimeters.convert(25.4, &Units::Inches).map(round),
+            Ok(1.0)
+        );
+        assert_eq!(
+            Units::Inches.convert(2.0, &Units::Millimeters).map(round),
+            Ok(50.8)
+        );
+        assert_eq!(
+            Units::Feet.convert(1000.0, &Units::Meters).map(round),
+            Ok(304.8)
+        );
+        assert_eq!(
+            Units::Millimeters
+                .convert(1500.0, &Units::Meters)
+                .map(round),
+            Ok(1.5)
+        );
+        assert_eq!(
+            Units::Hectares.convert(1.0, &Units::Acres).map(round),
+            Ok(2.471054)
+        );
+        assert_eq!(Units::Pascals.convert(1.0, &Units::Pascals), Ok(1.0));
+        assert_eq!(Units::Feet.dimension(), Dimension::Length);
+        assert!(matches!(Dimension::Area.base_units(), Units::SquareMeters));
+
+        assert!(Units::Kilometers
+            .convert(172.8, &Units::MetersPerSecond)
+            .is_err());
+
+        let error = Units::Percent
+            .convert(50.0, &Units::KiloPascals)
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Can't convert Percent, a ratio, to KiloPascals, a pressure"
+        );
+    }
+
+    #[test]
+    fn wind_speed_and_wind_run() {
+        let round = |value: f64| (value * 1e6).round() / 1e6;
+        // a daily wind run of 172.8 km is a mean speed of 2 m/s
+        assert_eq!(
+            Units::KilometersPerDay
+                .convert(172.8, &Units::MetersPerSecond)
+                .map(round),
+            Ok(2.0)
+        );
+        assert_eq!(
+            Units::MilesPerDay
+                .convert(100.0, &Units::MetersPerSecond)
+                .map(round),
+            Ok(1.862667)
+        );
+        assert_eq!(
+            Units::KilometersPerHour.convert(36.0, &Units::MetersPerSecond),
+            Ok(10.0)
+        );
+        assert_eq!(
+            Units::Knots
+                .convert(1.0, &Units::KilometersPerHour)
+                .map(round),
+            Ok(1.852)
+        );
+        assert_eq!(
+            Units::FeetPerSecond
+                .convert(10.0, &Units::MetersPerSecond)
+                .map(round),
+            Ok(3.048)
+        );
+        assert!(matches!(
+            Units::from_abbreviation("mi/day"),
+            Ok(Units::MilesPerDay)
+        ));
+    }
+
+    #[test]
+    fn temperatures_and_differences() {
+        let round = |value: f64| (value * 1e9).round() / 1e9;
+        assert_eq!(
+            Units::Kelvin.convert(288.15, &Units::Celsius).map(round),
+            Ok(15.0)
+        );
+        assert_eq!(
+            Units::Fahrenheit.convert(59.0, &Units::Kelvin).map(round),
+            Ok(288.15)
+        );
+
+        // a difference has no offset, 9 °F warmer is 5 °C warmer
+        assert_eq!(
+            Units::FahrenheitDifference.convert(9.0, &Units::CelsiusDifference),
+            Ok(5.0)
+        );
+        assert_eq!(
+            Units::KelvinDifference.convert(5.0, &Units::FahrenheitDifference),
+            Ok(9.0)
+        );
+        assert!(matches!(
+            Units::FahrenheitDifference.convert(9.0, &Units::Celsius),
+            Err(ClimateError::IncompatibleDimensions { .. })
+        ));
+    }
+
+    #[test]
+    fn pressure_units() {
+        let round = |value: f64| (value * 1e4).round() / 1e4;
+        assert_eq!(
+            Units::Millibars
+                .convert(1013.25, &Units::KiloPascals)
+                .map(round),
+            Ok(101.325)
+        );
+        assert_eq!(Units::Hectopascals.convert(1.0, &Units::Millibars), Ok(1.0));
+        assert_eq!(
+            Units::InchesOfMercury
+                .convert(29.92, &Units::KiloPascals)
+                .map(round),
+            Ok(101.3208)
+        );
+        assert_eq!(
+            Units::MillimetersOfMercury
+                .convert(760.0, &Units::Hectopascals)
+                .map(|v| (v * 100.0).round() / 100.0),
+            Ok(1013.25)
+        );
+    }
+
+    #[test]
+    fn units_are_parsed_loosely() {
+        let parsed = [
+            ("MM", Units::Millimeters),
+            ("Mm", Units::Millimeters),
+            ("inches", Units::Inches),
+            ("in.", Units::Inches),
+            ("deg C", Units::Celsius),
+            ("degrees_Fahrenheit", Units::Fahrenheit),
+            ("m s-1", Units::MetersPerSecond),
+            ("meters per second", Units::MetersPerSecond),
+            ("MJ m-2 d-1", Units::MegaJoulesPerSquareMeterPerDay),
+            ("W m-2", Units::WattsPerSquareMeter),
+            ("W/m^2", Units::WattsPerSquareMeter),
+            (" kPa ", Units::KiloPascals),
+            ("mm/d", Units::Millimeters),
+            ("MM/DAY", Units::Millimeters),
+            ("mm day^-1", Units::Millimeters),
+            ("kg m-2", Units::Millimeters),
+            ("kg/m2", Units::Millimeters),
+            ("kg m-2 d-1", Units::Millimeters),
+        ];
+        for (text, units) in parsed {
+            assert_eq!(Units::from_abbreviation(text), Ok(units), "{}", text);
+        }
+        for units in Units::all() {
+            assert_eq!(
+                Units::from_abbreviation(units.to_abbreviation()),
+                Ok(*units)
+            );
+            assert_eq!(Units::from_abbreviation(units.name()), Ok(*units));
+        }
+    }
+
+    #[test]
+    fn aliases_and_suggestions() {
+        assert!(Units::from_abbreviation("mm of water").is_err());
+        Units::register_alias("MM of Water", Units::Millimeters);
+        assert_eq!(
+            Units::from_abbreviation("mm of water"),
+            Ok(Units::Millimeters)
+        );
+
+        let error = Units::from_abbreviation("milimeters").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid unit: milimeters (did you mean mm?)"
+        );
+        assert_eq!(
+            Units::from_abbreviation("furlongs"),
+            Err(ClimateError::unknown_unit("furlongs"))
+        );
+    }
 }
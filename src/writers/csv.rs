@@ -0,0 +1,124 @@
+//! Writes the `Output` series to CSV for review in a spreadsheet.
+
+use crate::error::ClimateError;
+use crate::output::Output;
+use crate::writers::OutputUnits;
+use csv::Writer;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+
+/// Writes the series to a CSV file, replacing it if it exists.
+pub fn write_csv_file(
+    path: impl AsRef<Path>,
+    output: &[Output],
+    units: &OutputUnits,
+) -> Result<(), ClimateError> {
+    let path = path.as_ref();
+    let file = File::create(path)
+        .map_err(|e| ClimateError::Io(format!("Could not create {}: {}", path.display(), e)))?;
+    write_csv(file, output, units)
+}
+
+/// Writes the series as CSV. The first row holds the column names and the second the units each
+/// column is written in, followed by a row per record with its station and missing values left
+/// empty. Units that can't hold a column are returned as an error before anything is written.
+pub fn write_csv<W: Write>(
+    writer: W,
+    output: &[Output],
+    units: &OutputUnits,
+) -> Result<(), ClimateError> {
+    units.check()?;
+    let columns = units.columns();
+    let mut csv_writer = Writer::from_writer(writer);
+    let io_error = |e: csv::Error| ClimateError::Io(e.to_string());
+
+    let names = columns.iter().map(|(name, _, _)| *name);
+    csv_writer
+        .write_record(
+            ["date", "station_id", "station_name"]
+                .into_iter()
+                .chain(names),
+        )
+        .map_err(io_error)?;
+    let abbreviations = columns.iter().map(|(_, _, to)| to.to_abbreviation());
+    csv_writer
+        .write_record(["", "", ""].into_iter().chain(abbreviations))
+        .map_err(io_error)?;
+
+    for record in output {
+        let values = units.values(record)?.into_iter().map(|value| match value {
+            Some(value) => value.to_string(),
+            None => String::new(),
+        });
+        let station = [
+            record.get_date().format("%Y-%m-%d").to_string(),
+            record
+                .get_station_id()
+                .map(|id| id.to_string())
+                .unwrap_or_default(),
+            record.get_station_name().to_string(),
+        ];
+        csv_writer
+            .write_record(station.into_iter().chain(values))
+            .map_err(io_error)?;
+    }
+    csv_writer
+        .flush()
+        .map_err(|e| ClimateError::Io(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::units::Units;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn writes_names_units_and_values() {
+        let date = NaiveDate::from_ymd_opt(2023, 7, 6).unwrap();
+        let mut output = Output::new_with_values(
+            25.0,
+            15.0,
+            Some(84.0),
+            None,
+            None,
+            Some(1.4),
+            Some(f64::NAN),
+            Some(2.0),
+            Some(2.0),
+            100.0,
+            0.5,
+            date,
+        );
+        output.set_station_id(Some(3));
+        output.set_station_name("Mead, NE".to_string());
+        let units = OutputUnits {
+            temperature: Units::Fahrenheit,
+            height: Units::Centimeters,
+            ..OutputUnits::default()
+        };
+        let mut written = Vec::new();
+        write_csv(&mut written, &[output], &units).unwrap();
+
+        let text = String::from_utf8(written).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(
+            lines[0],
+            "date,station_id,station_name,tmin,tmax,rhmin,rhmax,dewpoint,ea,rs,ws,wz,z,latitude"
+        );
+        assert_eq!(lines[1], ",,,°F,°F,%,%,°F,kPa,MJ/m²/day,m/s,cm,cm,rad");
+        // rs isn't finite and is left empty like a missing value
+        assert_eq!(
+            lines[2],
+            "2023-07-06,3,\"Mead, NE\",59,77,,84,,1.4,,2,200,10000,0.5"
+        );
+
+        let units = OutputUnits {
+            radiation: Units::Feet,
+            ..OutputUnits::default()
+        };
+        let error = write_csv(Vec::new(), &[], &units).unwrap_err();
+        assert_eq!(error.variable(), Some("rs"));
+    }
+}
@@ -0,0 +1,146 @@
+//! Writes the `Output` series as JSON Lines, one object per record. Needs the `serde` feature.
+
+use crate::error::ClimateError;
+use crate::output::Output;
+use crate::writers::OutputUnits;
+use serde::Serialize;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// One line of the file, with the units of each value after it.
+#[derive(Serialize)]
+struct Row<'a> {
+    date: String,
+    station_id: Option<i32>,
+    station_name: &'a str,
+    tmin: Option<f64>,
+    tmin_units: &'a str,
+    tmax: Option<f64>,
+    tmax_units: &'a str,
+    rhmin: Option<f64>,
+    rhmin_units: &'a str,
+    rhmax: Option<f64>,
+    rhmax_units: &'a str,
+    dewpoint: Option<f64>,
+    dewpoint_units: &'a str,
+    ea: Option<f64>,
+    ea_units: &'a str,
+    rs: Option<f64>,
+    rs_units: &'a str,
+    ws: Option<f64>,
+    ws_units: &'a str,
+    wz: Option<f64>,
+    wz_units: &'a str,
+    z: Option<f64>,
+    z_units: &'a str,
+    latitude: Option<f64>,
+    latitude_units: &'a str,
+}
+
+/// Writes the series to a JSON Lines file, replacing it if it exists.
+pub fn write_json_lines_file(
+    path: impl AsRef<Path>,
+    output: &[Output],
+    units: &OutputUnits,
+) -> Result<(), ClimateError> {
+    let path = path.as_ref();
+    let file = File::create(path)
+        .map_err(|e| ClimateError::Io(format!("Could not create {}: {}", path.display(), e)))?;
+    write_json_lines(BufWriter::new(file), output, units)
+}
+
+/// Writes the series as JSON Lines. Each record is an object with the ISO 8601 date, the station
+/// id and name, each value and, like `DailyData`, the units it is in under `<name>_units`;
+/// missing values are `null`. Units that can't hold a column are returned as an error before
+/// anything is written.
+pub fn write_json_lines<W: Write>(
+    mut writer: W,
+    output: &[Output],
+    units: &OutputUnits,
+) -> Result<(), ClimateError> {
+    units.check()?;
+    let io_error = |e: std::io::Error| ClimateError::Io(e.to_string());
+
+    for record in output {
+        let [tmin, tmax, rhmin, rhmax, dewpoint, ea, rs, ws, wz, z, latitude] =
+            units.values(record)?[..]
+        else {
+            unreachable!("there is a value for every column");
+        };
+        let row = Row {
+            date: record.get_date().format("%Y-%m-%d").to_string(),
+            station_id: record.get_station_id(),
+            station_name: record.get_station_name(),
+            tmin,
+            tmin_units: units.temperature.to_abbreviation(),
+            tmax,
+            tmax_units: units.temperature.to_abbreviation(),
+            rhmin,
+            rhmin_units: units.humidity.to_abbreviation(),
+            rhmax,
+            rhmax_units: units.humidity.to_abbreviation(),
+            dewpoint,
+            dewpoint_units: units.temperature.to_abbreviation(),
+            ea,
+            ea_units: units.vapor_pressure.to_abbreviation(),
+            rs,
+            rs_units: units.radiation.to_abbreviation(),
+            ws,
+            ws_units: units.wind_speed.to_abbreviation(),
+            wz,
+            wz_units: units.height.to_abbreviation(),
+            z,
+            z_units: units.height.to_abbreviation(),
+            latitude,
+            latitude_units: units.latitude.to_abbreviation(),
+        };
+        serde_json::to_writer(&mut writer, &row).map_err(|e| ClimateError::Io(e.to_string()))?;
+        writeln!(writer).map_err(io_error)?;
+    }
+    writer.flush().map_err(io_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::units::Units;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn writes_a_line_per_record() {
+        let output: Vec<Output> = [6, 7]
+            .iter()
+            .map(|day| {
+                let ws = (*day == 6).then_some(4.0);
+                let rs = (*day == 7).then_some(f64::NAN);
+                let date = NaiveDate::from_ymd_opt(2023, 7, *day).unwrap();
+                let mut output = Output::new_with_values(
+                    30.0, 20.0, None, None, None, None, rs, ws, None, 350.0, 0.7, date,
+                );
+                output.set_station_id(Some(3));
+                output.set_station_name("Mead".to_string());
+                output
+            })
+            .collect();
+        let units = OutputUnits {
+            wind_speed: Units::KilometersPerHour,
+            ..OutputUnits::default()
+        };
+        let mut written = Vec::new();
+        write_json_lines(&mut written, &output, &units).unwrap();
+
+        let text = String::from_utf8(written).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with(
+            "{\"date\":\"2023-07-06\",\"station_id\":3,\"station_name\":\"Mead\",\"tmin\":20.0,\
+             \"tmin_units\":\"°C\","
+        ));
+        assert!(lines[0].contains("\"ws\":14.4,\"ws_units\":\"km/h\""));
+        assert!(lines[1].contains("\"ws\":null,\"ws_units\":\"km/h\""));
+        // a value that isn't finite is written as missing
+        assert!(lines[1].contains("\"rs\":null,\"rs_units\":\"MJ/m²/day\""));
+        assert!(lines[1].ends_with("\"latitude\":0.7,\"latitude_units\":\"rad\"}"));
+    }
+}
@@ -0,0 +1,101 @@
+//! Writers that emit the refet-ready `Output` series for review outside of the crate.
+
+pub mod csv;
+#[cfg(feature = "serde")]
+pub mod json;
+
+use crate::error::ClimateError;
+use crate::output::Output;
+use crate::units::Units;
+
+/// The units each column is written in. `Output` holds SI values, they are converted back with
+/// `Units::convert` so any units of the same dimension can be chosen. The default writes the
+/// values as `Output` holds them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OutputUnits {
+    /// tmin, tmax and dewpoint
+    pub temperature: Units,
+    /// rhmin and rhmax
+    pub humidity: Units,
+    /// ea
+    pub vapor_pressure: Units,
+    /// rs, a daily total
+    pub radiation: Units,
+    /// ws
+    pub wind_speed: Units,
+    /// wz and z
+    pub height: Units,
+    pub latitude: Units,
+}
+
+impl Default for OutputUnits {
+    fn default() -> Self {
+        OutputUnits {
+            temperature: Units::Celsius,
+            humidity: Units::Percent,
+            vapor_pressure: Units::KiloPascals,
+            radiation: Units::MegaJoulesPerSquareMeterPerDay,
+            wind_speed: Units::MetersPerSecond,
+            height: Units::Meters,
+            latitude: Units::Radians,
+        }
+    }
+}
+
+impl OutputUnits {
+    /// The columns written after the date: the name, the units `Output` holds the value in and
+    /// the units it is written in.
+    pub(crate) fn columns(&self) -> [(&'static str, Units, Units); 11] {
+        let defaults = OutputUnits::default();
+        [
+            ("tmin", defaults.temperature, self.temperature),
+            ("tmax", defaults.temperature, self.temperature),
+            ("rhmin", defaults.humidity, self.humidity),
+            ("rhmax", defaults.humidity, self.humidity),
+            ("dewpoint", defaults.temperature, self.temperature),
+            ("ea", defaults.vapor_pressure, self.vapor_pressure),
+            ("rs", defaults.radiation, self.radiation),
+            ("ws", defaults.wind_speed, self.wind_speed),
+            ("wz", defaults.height, self.height),
+            ("z", defaults.height, self.height),
+            ("latitude", defaults.latitude, self.latitude),
+        ]
+    }
+
+    /// Checks every column can be converted to its chosen units before anything is written.
+    pub(crate) fn check(&self) -> Result<(), ClimateError> {
+        for (name, from, to) in self.columns() {
+            from.convert(0.0, &to).map_err(|e| e.with_variable(name))?;
+        }
+        Ok(())
+    }
+
+    /// The values of a record in the order of `columns`, converted to the chosen units. Values
+    /// that aren't finite are missing.
+    pub(crate) fn values(&self, output: &Output) -> Result<Vec<Option<f64>>, ClimateError> {
+        let values = [
+            Some(output.get_tmin()),
+            Some(output.get_tmax()),
+            output.get_rhmin(),
+            output.get_rhmax(),
+            output.get_dewpoint(),
+            output.get_ea(),
+            output.get_rs(),
+            output.get_ws(),
+            Some(output.get_wz()),
+            Some(output.get_z()),
+            Some(output.get_latitude()),
+        ];
+        self.columns()
+            .iter()
+            .zip(values)
+            .map(|((name, from, to), value)| {
+                value
+                    .filter(|value| value.is_finite())
+                    .map(|value| from.convert(value, to))
+                    .transpose()
+                    .map_err(|e| e.with_context(name, output.get_date()))
+            })
+            .collect()
+    }
+}